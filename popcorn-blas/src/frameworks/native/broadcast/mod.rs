@@ -1,40 +1,137 @@
 pub mod shape;
 pub mod iter;
 
-use std::cmp;
 pub use self::shape::*;
 pub use self::iter::{DenseStrideIter, DenseBroadcastIter};
 
 use popcorn::buffer::Error;
 
+// Broadcast two dense buffers against each other with NumPy rules:
+// shapes are aligned from the trailing dimension and size 1 dimensions
+// stretch to match the other shape. The trailing `chop` dimensions are
+// not iterated over, each item of the returned iterators is a contiguous
+// slice covering them, so those must match exactly in both shapes. The
+// returned shape is the broadcast shape without the chopped dimensions.
 pub fn try_new_broadcast<'a, T: 'a>(shape_a: &[usize],
                                     a: &'a [T],
                                     shape_b: &[usize],
                                     b: &'a [T],
                                     chop: usize) -> Result<(Vec<usize>, DenseBroadcastIter<'a, T>, DenseBroadcastIter<'a, T>), Error> {
-  if !compatible(shape_a, shape_b) {
-    return Err(Error::InvalidBroadcast)
+  if let Some(axis) = incompatible_axis(shape_a, shape_b) {
+    return Err(Error::BroadcastError {
+      shape_a: shape_a.to_vec(),
+      shape_b: shape_b.to_vec(),
+      axis: axis
+    })
   }
 
   let mut bshape = target_shape(shape_a, shape_b);
-  let strides_a = DenseStrideIter::new(shape_a);
-  let strides_b = DenseStrideIter::new(shape_b);
+  let len = bshape.len();
+  if chop > len {
+    return Err(Error::InvalidBroadcast)
+  }
+
+  let padded_a = pad_shape(shape_a, len);
+  let padded_b = pad_shape(shape_b, len);
 
-  let mut bdims_a = BroadcastDimension::shape_from_iters(shape_a.iter().map(|x| *x),
+  if let Some(axis) = (len - chop..len).find(|&i| padded_a[i] != padded_b[i]) {
+    return Err(Error::BroadcastError {
+      shape_a: shape_a.to_vec(),
+      shape_b: shape_b.to_vec(),
+      axis: axis
+    })
+  }
+
+  let mut bdims_a = BroadcastDimension::shape_from_iters(padded_a.iter().map(|x| *x),
   bshape.iter().map(|x| *x),
-  strides_a);
+  DenseStrideIter::new(&padded_a));
 
-  let mut bdims_b = BroadcastDimension::shape_from_iters(shape_b.iter().map(|x| *x),
+  let mut bdims_b = BroadcastDimension::shape_from_iters(padded_b.iter().map(|x| *x),
   bshape.iter().map(|x| *x),
-  strides_b);
+  DenseStrideIter::new(&padded_b));
+
+  bdims_a.truncate(len - chop);
+  bdims_b.truncate(len - chop);
+  bshape.truncate(len - chop);
 
-  let len = bdims_a.len();
-  bdims_a.truncate(cmp::max(1, len - chop));
-  bdims_b.truncate(cmp::max(1, len - chop));
-  bshape.truncate(cmp::max(1, len - chop));
+  // With no dimensions left to iterate, yield the chopped dimensions
+  // of each buffer once
+  if bdims_a.is_empty() {
+    let size = padded_a.iter().product();
+    bdims_a.push(BroadcastDimension { size: size, stride: 0, target: 1 });
+    bdims_b.push(BroadcastDimension { size: size, stride: 0, target: 1 });
+  }
 
   let iter_a = DenseBroadcastIter::new(bdims_a, a);
   let iter_b = DenseBroadcastIter::new(bdims_b, b);
 
   Ok((bshape, iter_a, iter_b))
 }
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_broadcast_stretches_unit_dims() {
+    let a = [1, 2, 3];
+    let b = [10, 20, 30, 40];
+
+    let (bshape, iter_a, iter_b) = try_new_broadcast(&[3, 1], &a, &[1, 4], &b, 0).unwrap();
+    assert_eq!(bshape, vec![3, 4]);
+
+    let sums: Vec<i32> = iter_a.zip(iter_b).map(|(x, y)| x[0] + y[0]).collect();
+    assert_eq!(sums, vec![11, 21, 31, 41,
+                          12, 22, 32, 42,
+                          13, 23, 33, 43]);
+  }
+
+  #[test]
+  fn test_broadcast_aligns_trailing_dims() {
+    let a = [1, 2, 3, 4, 5, 6];
+    let b = [10, 20, 30];
+
+    let (bshape, iter_a, iter_b) = try_new_broadcast(&[2, 3], &a, &[3], &b, 1).unwrap();
+    assert_eq!(bshape, vec![2]);
+
+    let pairs: Vec<(&[i32], &[i32])> = iter_a.zip(iter_b).collect();
+    assert_eq!(pairs, vec![(&a[0..3], &b[..]), (&a[3..6], &b[..])]);
+  }
+
+  #[test]
+  fn test_broadcast_incompatible() {
+    let a = [0; 6];
+    let b = [0; 8];
+
+    match try_new_broadcast(&[3, 2], &a, &[4, 2], &b, 0) {
+      Err(Error::BroadcastError { shape_a, shape_b, axis }) => {
+        assert_eq!(shape_a, vec![3, 2]);
+        assert_eq!(shape_b, vec![4, 2]);
+        assert_eq!(axis, 0);
+      },
+      _ => panic!("expected a broadcast error")
+    }
+  }
+
+  #[test]
+  fn test_broadcast_error_axis() {
+    let a = [0; 6];
+    let b = [0; 4];
+
+    match try_new_broadcast(&[2, 3], &a, &[4], &b, 0) {
+      Err(Error::BroadcastError { axis, .. }) => assert_eq!(axis, 1),
+      _ => panic!("expected a broadcast error")
+    }
+  }
+
+  #[test]
+  fn test_broadcast_chopped_dims_must_match() {
+    let a = [0; 6];
+    let b = [0; 2];
+
+    match try_new_broadcast(&[2, 3], &a, &[2, 1], &b, 1) {
+      Err(Error::BroadcastError { axis, .. }) => assert_eq!(axis, 1),
+      _ => panic!("expected a broadcast error")
+    }
+  }
+}
@@ -36,36 +36,36 @@ impl BroadcastDimension {
 
 pub fn compatible<'a>(shape1: &'a [usize],
                       shape2: &'a [usize]) -> bool {
-  shape1.iter().rev().zip(shape2.iter().rev()).all(|(&a, &b)| {
-    a == b || a == 1 || b == 1
-  })
+  incompatible_axis(shape1, shape2).is_none()
 }
 
-pub fn target_shape<'a>(shape1: &'a [usize],
-                        shape2: &'a [usize]) -> Vec<usize> {
-  let len1 = shape1.len();
-  let len2 = shape2.len();
+// Find the first axis, counted in the broadcast shape, where the
+// two shapes can not be broadcast against each other
+pub fn incompatible_axis<'a>(shape1: &'a [usize],
+                             shape2: &'a [usize]) -> Option<usize> {
+  let len = cmp::max(shape1.len(), shape2.len());
+
+  shape1.iter().rev().zip(shape2.iter().rev()).position(|(&a, &b)| {
+    !(a == b || a == 1 || b == 1)
+  }).map(|i| len - 1 - i)
+}
 
-  match len1.cmp(&len2) {
-    cmp::Ordering::Equal => {
-      shape1.iter().zip(shape2.iter()).
-        map(|(&a, &b)| cmp::max(a, b)).
-        collect()
-    },
-    cmp::Ordering::Less => {
-      let one: usize = 1;
+// Left pad a shape with 1s up to len dimensions, aligning it
+// from the trailing dimension
+pub fn pad_shape<'a>(shape: &'a [usize],
+                     len: usize) -> Vec<usize> {
+  repeat(1).take(len.saturating_sub(shape.len())).
+    chain(shape.iter().map(|x| *x)).
+    collect()
+}
 
-      repeat(&one).take(len2 - len1).chain(shape1.iter()).zip(shape2.iter()).
-        map(|(&a, &b)| cmp::max(a, b)).
-        collect()
-    },
-    cmp::Ordering::Greater => {
-      let one: usize = 1;
+pub fn target_shape<'a>(shape1: &'a [usize],
+                        shape2: &'a [usize]) -> Vec<usize> {
+  let len = cmp::max(shape1.len(), shape2.len());
 
-      shape1.iter().zip(repeat(&one).take(len1 - len2).chain(shape2.iter())).
-        map(|(&a, &b)| cmp::max(a, b)).
-        collect()
-    }
-  }
+  // Size 1 dimensions stretch to match the other shape
+  pad_shape(shape1, len).into_iter().zip(pad_shape(shape2, len).into_iter()).
+    map(|(a, b)| if a == 1 { b } else { a }).
+    collect()
 }
 
@@ -0,0 +1,63 @@
+use popcorn::frameworks::native::Framework;
+use popcorn::backend::Backend;
+use popcorn::num::Float;
+use operation::*;
+use frameworks::native::par;
+use futures::Future;
+use popcorn::buffer::{Buffer, LockedBuffer, Error};
+
+fn relu<T: Float>(x: T) -> T {
+  if x > T::zero() { x } else { T::zero() }
+}
+
+fn sigmoid<T: Float>(x: T) -> T {
+  T::one() / (T::one() + (T::zero() - x).exp())
+}
+
+fn tanh<T: Float>(x: T) -> T {
+  x.tanh()
+}
+
+fn map<B: Backend<Framework>,
+       T: Float + Sync + Send + 'static>(backend: &B,
+                                         x: LockedBuffer<T>,
+                                         f: fn(T) -> T) ->
+  Box<Future<Item=LockedBuffer<T>, Error=Error>> {
+    // Step 1. Sync the input buffer to the required device
+    let dev = backend.device();
+    let r = x.sync(dev);
+
+    // Step 2. Apply f to every element of x on the cpu pool
+    let dev = backend.device().clone();
+    let pool = backend.device().pool().clone();
+    Box::new(r.and_then(move |x| {
+      pool.spawn_fn(move || {
+        let n_x: &[T] = try!(try!(x.native_memory(&dev)).try_as_slice());
+        let mut y = try!(try!(Buffer::with_capacity_native(&dev, n_x.len())).try_lock());
+
+        {
+          let n_y: &mut [T] = try!(try!(y.native_memory_mut(&dev)).try_as_mut_slice());
+          par::map(n_x, n_y, |x| f(*x));
+        }
+
+        Ok(y)
+      })
+    }))
+  }
+
+impl<B: Backend<Framework>, T: Float + Sync + Send + 'static> ActivationOperation<T> for B {
+  fn relu(&self,
+          x: LockedBuffer<T>) -> Box<Future<Item=LockedBuffer<T>, Error=Error>> {
+    map(self, x, relu)
+  }
+
+  fn sigmoid(&self,
+             x: LockedBuffer<T>) -> Box<Future<Item=LockedBuffer<T>, Error=Error>> {
+    map(self, x, sigmoid)
+  }
+
+  fn tanh(&self,
+          x: LockedBuffer<T>) -> Box<Future<Item=LockedBuffer<T>, Error=Error>> {
+    map(self, x, tanh)
+  }
+}
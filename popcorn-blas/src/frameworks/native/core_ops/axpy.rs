@@ -0,0 +1,60 @@
+use popcorn::frameworks::native::Framework;
+use popcorn::backend::Backend;
+use operation::*;
+use futures::{Future, IntoFuture};
+use popcorn::buffer::{LockedBuffer, Error};
+#[cfg(not(feature = "pure"))]
+use blas_sys::c::cblas_saxpy;
+
+pub trait Axpy where Self: Sized {
+  // y = alpha * x + y
+  fn axpy(alpha: Self, x: &[Self], y: &mut [Self]);
+}
+
+#[cfg(not(feature = "pure"))]
+impl Axpy for f32 {
+  fn axpy(alpha: Self, x: &[Self], y: &mut [Self]) {
+    unsafe {
+      cblas_saxpy(x.len() as i32, alpha, x.as_ptr(), 1, y.as_mut_ptr(), 1)
+    }
+  }
+}
+
+#[cfg(feature = "pure")]
+impl Axpy for f32 {
+  fn axpy(alpha: Self, x: &[Self], y: &mut [Self]) {
+    for (y, x) in y.iter_mut().zip(x.iter()) {
+      *y = alpha * x + *y;
+    }
+  }
+}
+
+impl<B: Backend<Framework>, T: Axpy + Sync + Copy + Sized + Send + 'static> AxpyOperation<T> for B {
+  fn axpy(&self,
+          alpha: T,
+          x: LockedBuffer<T>,
+          y: LockedBuffer<T>) -> Box<Future<Item=LockedBuffer<T>, Error=Error>> {
+    if x.size() != y.size() {
+      return Box::new(Err(Error::InvalidBroadcast).into_future())
+    }
+
+    // Step 1. Sync all input buffers to the required device
+    let dev = self.device();
+    let r = x.sync(dev).join(y.sync(dev));
+
+    // Step 2. Update y in place on the cpu pool
+    let dev = self.device().clone();
+    let pool = self.device().pool().clone();
+    Box::new(r.and_then(move |(x, mut y)| {
+      pool.spawn_fn(move || {
+        {
+          let n_x: &[T] = try!(try!(x.native_memory(&dev)).try_as_slice());
+          let n_y: &mut [T] = try!(try!(y.native_memory_mut(&dev)).try_as_mut_slice());
+          T::axpy(alpha, n_x, n_y);
+        }
+
+        Ok(y)
+      })
+    }))
+  }
+}
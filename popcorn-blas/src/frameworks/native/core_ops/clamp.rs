@@ -0,0 +1,42 @@
+use popcorn::frameworks::native::Framework;
+use popcorn::backend::Backend;
+use operation::*;
+use frameworks::native::par;
+use futures::{Future, IntoFuture};
+use popcorn::buffer::{LockedBuffer, Error};
+
+impl<B: Backend<Framework>, T: PartialOrd + Sync + Copy + Sized + Send + 'static> ClampOperation<T> for B {
+  fn clamp(&self,
+           x: LockedBuffer<T>,
+           min: T,
+           max: T) -> Box<Future<Item=LockedBuffer<T>, Error=Error>> {
+    if min > max {
+      return Box::new(Err(Error::InvalidRange).into_future())
+    }
+
+    // Step 1. Sync the input buffer to the required device
+    let dev = self.device();
+    let r = x.sync(dev);
+
+    // Step 2. Clamp x in place on the cpu pool, which also makes
+    //   this device hold the latest copy
+    let dev = self.device().clone();
+    let pool = self.device().pool().clone();
+    Box::new(r.and_then(move |mut x| {
+      pool.spawn_fn(move || {
+        {
+          let n_x: &mut [T] = try!(try!(x.native_memory_mut(&dev)).try_as_mut_slice());
+          par::update(n_x, |v| {
+            if *v < min {
+              *v = min;
+            } else if *v > max {
+              *v = max;
+            }
+          });
+        }
+
+        Ok(x)
+      })
+    }))
+  }
+}
@@ -1,24 +1,104 @@
 use super::super::broadcast;
-use popcorn::frameworks::native::Framework;
+use popcorn::frameworks::native::{self, Framework};
 use popcorn::backend::Backend;
 use operation::*;
-use futures::Future;
+use futures::{future, Future};
 use popcorn::buffer::{Buffer, LockedBuffer, Error};
+use std::cmp;
 use std::fmt;
-use blas_sys::c::cblas_sdot;
+use std::mem;
+use std::sync::Arc;
+#[cfg(not(feature = "pure"))]
+use blas_sys::c::{cblas_sdot, cblas_ddot};
+#[cfg(all(feature = "complex", not(feature = "pure")))]
+use blas_sys::c::{cblas_cdotu_sub, cblas_zdotu_sub};
+#[cfg(feature = "complex")]
+use num_complex::Complex;
 
+/// Panics if `a` and `b` differ in length.
 pub trait Dot where Self: Sized {
   fn dot(a: &[Self], b: &[Self]) -> Self;
 }
 
+#[cfg(not(feature = "pure"))]
 impl Dot for f32 {
   fn dot(a: &[Self], b: &[Self]) -> Self {
+    assert_eq!(a.len(), b.len());
     unsafe {
       cblas_sdot(a.len() as i32, a.as_ptr(), 1, b.as_ptr(), 1)
     }
   }
 }
 
+#[cfg(not(feature = "pure"))]
+impl Dot for f64 {
+  fn dot(a: &[Self], b: &[Self]) -> Self {
+    assert_eq!(a.len(), b.len());
+    unsafe {
+      cblas_ddot(a.len() as i32, a.as_ptr(), 1, b.as_ptr(), 1)
+    }
+  }
+}
+
+#[cfg(feature = "pure")]
+impl Dot for f32 {
+  fn dot(a: &[Self], b: &[Self]) -> Self {
+    a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+  }
+}
+
+#[cfg(feature = "pure")]
+impl Dot for f64 {
+  fn dot(a: &[Self], b: &[Self]) -> Self {
+    a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+  }
+}
+
+// Complex elements are two floats in a row, the layout BLAS expects,
+// and are multiplied without conjugating either side
+#[cfg(all(feature = "complex", not(feature = "pure")))]
+impl Dot for Complex<f32> {
+  fn dot(a: &[Self], b: &[Self]) -> Self {
+    let mut r = Complex::new(0.0, 0.0);
+    assert_eq!(a.len(), b.len());
+    unsafe {
+      cblas_cdotu_sub(a.len() as i32, a.as_ptr() as *const _, 1, b.as_ptr() as *const _, 1,
+                      &mut r as *mut Complex<f32> as *mut _);
+    }
+    r
+  }
+}
+
+#[cfg(all(feature = "complex", not(feature = "pure")))]
+impl Dot for Complex<f64> {
+  fn dot(a: &[Self], b: &[Self]) -> Self {
+    let mut r = Complex::new(0.0, 0.0);
+    assert_eq!(a.len(), b.len());
+    unsafe {
+      cblas_zdotu_sub(a.len() as i32, a.as_ptr() as *const _, 1, b.as_ptr() as *const _, 1,
+                      &mut r as *mut Complex<f64> as *mut _);
+    }
+    r
+  }
+}
+
+#[cfg(all(feature = "complex", feature = "pure"))]
+impl Dot for Complex<f32> {
+  fn dot(a: &[Self], b: &[Self]) -> Self {
+    a.iter().zip(b.iter()).fold(Complex::new(0.0, 0.0), |acc, (x, y)| acc + x * y)
+  }
+}
+
+#[cfg(all(feature = "complex", feature = "pure"))]
+impl Dot for Complex<f64> {
+  fn dot(a: &[Self], b: &[Self]) -> Self {
+    a.iter().zip(b.iter()).fold(Complex::new(0.0, 0.0), |acc, (x, y)| acc + x * y)
+  }
+}
+
+// Fewest output rows worth handing to a pool task of their own
+const MIN_CHUNK_ROWS: usize = 64;
+
 impl<B: Backend<Framework>, T: Dot + fmt::Debug + Sync + Copy + Sized + Send + 'static> DotOperation<T> for B {
   fn bcast_dot(&self,
                shape_a: LockedBuffer<usize>,
@@ -31,41 +111,91 @@ impl<B: Backend<Framework>, T: Dot + fmt::Debug + Sync + Copy + Sized + Send + '
       let ar = shape_a.sync(dev).join(a.sync(dev));
       let br = shape_b.sync(dev).join(b.sync(dev));
 
-      // Step 2. Convert all memory to native memory and execute the
-      //   broadcasted dot operation on the cpu pool
+      // Step 2. Work out the broadcast shape and where each of its rows
+      //   starts in both inputs, then split the rows into chunks, one
+      //   per pool thread
       let dev = self.device().clone();
       let pool = self.device().pool().clone();
-      Box::new(ar.join(br).and_then(move |((shape_a, a), (shape_b, b))| {
-        pool.spawn_fn(move || {
-          let (shape_c, c) = {
-            let n_shape_a: &[usize] = try!(try!(shape_a.native_memory(&dev)).try_as_slice());
-            let n_a: &[T] = try!(try!(a.native_memory(&dev)).try_as_slice());
-            let n_shape_b: &[usize] = try!(try!(shape_b.native_memory(&dev)).try_as_slice());
-            let n_b: &[T] = try!(try!(b.native_memory(&dev)).try_as_slice());
-            //let n_shape_c: &mut [usize] = try!(try!(shape_c.native_memory_mut(&dev)).try_as_mut_slice());
-            //let n_c: &mut [T] = try!(try!(c.native_memory_mut(&dev)).try_as_mut_slice());
-
-            let (mut bshape, iter_a, iter_b) = try!(broadcast::try_new_broadcast(n_shape_a, n_a, n_shape_b, n_b, 1));
-            bshape.pop();
+      let inputs = ar.join(br).and_then(move |((shape_a, a), (shape_b, b))| {
+        let task_pool = pool.clone();
+        task_pool.spawn_fn(move || {
+          let rows = try!(broadcast_rows(&dev, &shape_a, &a, &shape_b, &b));
+          let n = rows.offsets.len();
+          let chunks = cmp::max(1, cmp::min(dev.threads(), (n + MIN_CHUNK_ROWS - 1) / MIN_CHUNK_ROWS));
+          let chunk_rows = cmp::max(1, (n + chunks - 1) / chunks);
 
-            let mut c = try!(try!(Buffer::with_capacity_native(&dev, bshape.iter().sum())).try_lock());
-            let shape_c = try!(try!(Buffer::from_vec_native(&dev, bshape)).try_lock());
-
-            {
-              let n_c: &mut [T] = try!(try!(c.native_memory_mut(&dev)).try_as_mut_slice());
+          Ok((Arc::new((a, b, rows.offsets)), rows.shape, rows.len, chunk_rows, dev, pool))
+        })
+      });
 
-              let r_iter = iter_a.zip(iter_b).map(|(a, b)| T::dot(a, b));
+      // Step 3. Compute every chunk of rows as its own task on the cpu
+      //   pool, then gather them in order into the output
+      Box::new(inputs.and_then(move |(inputs, bshape, len, chunk_rows, dev, pool)| {
+        let rows = inputs.2.len();
+        let tasks: Vec<_> = (0..rows).step_by(chunk_rows).map(|start| {
+          let inputs = inputs.clone();
+          let dev = dev.clone();
+          pool.spawn_fn(move || {
+            let (ref a, ref b, ref offsets) = *inputs;
+            let n_a: &[T] = try!(try!(a.native_memory(&dev)).try_as_slice());
+            let n_b: &[T] = try!(try!(b.native_memory(&dev)).try_as_slice());
+            let end = cmp::min(start + chunk_rows, offsets.len());
+            Ok(offsets[start..end].iter().map(|&(oa, ob)| {
+              T::dot(&n_a[oa..oa + len], &n_b[ob..ob + len])
+            }).collect::<Vec<T>>())
+          })
+        }).collect();
 
-              for(v1, v2) in r_iter.zip(n_c.iter_mut()) {
-                *v2 = v1;
-              }
+        future::join_all(tasks).and_then(move |chunks| {
+          let mut c = try!(try!(Buffer::with_capacity_native(&dev, rows)).try_lock());
+          {
+            let n_c: &mut [T] = try!(try!(c.native_memory_mut(&dev)).try_as_mut_slice());
+            for (dst, chunk) in n_c.chunks_mut(chunk_rows).zip(chunks.iter()) {
+              dst.copy_from_slice(chunk);
             }
+          }
 
-            (shape_c, c)
-          };
-
+          let shape_c = try!(try!(Buffer::from_vec_native(&dev, bshape)).try_lock());
           Ok((shape_c, c))
         })
       }))
     }
 }
+
+// The rows of both inputs that are paired up by the broadcast
+struct Rows {
+  // Output shape, the broadcast shape without the reduced dimension
+  shape: Vec<usize>,
+  // Length of every row
+  len: usize,
+  // Element offsets of each pair of rows into the two inputs
+  offsets: Vec<(usize, usize)>
+}
+
+// The chopped trailing dimension is the one being reduced, so every
+// item of the broadcast is a pair of rows to take the dot product of
+fn broadcast_rows<T: 'static + Copy + Send>(dev: &native::Device,
+                                            shape_a: &LockedBuffer<usize>,
+                                            a: &LockedBuffer<T>,
+                                            shape_b: &LockedBuffer<usize>,
+                                            b: &LockedBuffer<T>) -> Result<Rows, Error> {
+  let n_shape_a: &[usize] = try!(try!(shape_a.native_memory(dev)).try_as_slice());
+  let n_a: &[T] = try!(try!(a.native_memory(dev)).try_as_slice());
+  let n_shape_b: &[usize] = try!(try!(shape_b.native_memory(dev)).try_as_slice());
+  let n_b: &[T] = try!(try!(b.native_memory(dev)).try_as_slice());
+
+  let (shape, iter_a, iter_b) = try!(broadcast::try_new_broadcast(n_shape_a, n_a, n_shape_b, n_b, 1));
+  let len = n_shape_a.last().cloned().unwrap_or(1);
+  let offsets = iter_a.zip(iter_b).map(|(x, y)| (offset(n_a, x), offset(n_b, y))).collect();
+
+  Ok(Rows {
+    shape: shape,
+    len: len,
+    offsets: offsets
+  })
+}
+
+// Where `row`, a slice of `buf`, starts in it
+fn offset<T>(buf: &[T], row: &[T]) -> usize {
+  (row.as_ptr() as usize - buf.as_ptr() as usize) / mem::size_of::<T>()
+}
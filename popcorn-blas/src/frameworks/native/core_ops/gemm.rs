@@ -0,0 +1,82 @@
+use popcorn::frameworks::native::Framework;
+use popcorn::backend::Backend;
+use operation::*;
+use futures::Future;
+use popcorn::buffer::{Buffer, LockedBuffer, Error};
+#[cfg(not(feature = "pure"))]
+use blas_sys::c::{cblas_sgemm, Layout, Transpose};
+
+pub trait Gemm where Self: Sized {
+  // C = A·B for row major A (m x k), B (k x n) and C (m x n)
+  fn gemm(m: usize, n: usize, k: usize, a: &[Self], b: &[Self], c: &mut [Self]);
+}
+
+#[cfg(not(feature = "pure"))]
+impl Gemm for f32 {
+  fn gemm(m: usize, n: usize, k: usize, a: &[Self], b: &[Self], c: &mut [Self]) {
+    unsafe {
+      cblas_sgemm(Layout::RowMajor, Transpose::None, Transpose::None,
+                  m as i32, n as i32, k as i32,
+                  1.0, a.as_ptr(), k as i32,
+                  b.as_ptr(), n as i32,
+                  0.0, c.as_mut_ptr(), n as i32)
+    }
+  }
+}
+
+#[cfg(feature = "pure")]
+impl Gemm for f32 {
+  fn gemm(m: usize, n: usize, k: usize, a: &[Self], b: &[Self], c: &mut [Self]) {
+    for i in 0..m {
+      for j in 0..n {
+        c[i * n + j] = (0..k).map(|l| a[i * k + l] * b[l * n + j]).sum();
+      }
+    }
+  }
+}
+
+impl<B: Backend<Framework>, T: Gemm + Sync + Copy + Sized + Send + 'static> GemmOperation<T> for B {
+  fn bcast_gemm(&self,
+                shape_a: LockedBuffer<usize>,
+                a: LockedBuffer<T>,
+                shape_b: LockedBuffer<usize>,
+                b: LockedBuffer<T>) ->
+    Box<Future<Item=(LockedBuffer<usize>, LockedBuffer<T>), Error=Error>> {
+      // Step 1. Sync all input buffers to the required device
+      let dev = self.device();
+      let ar = shape_a.sync(dev).join(a.sync(dev));
+      let br = shape_b.sync(dev).join(b.sync(dev));
+
+      // Step 2. Validate the matrix shapes and execute the
+      //   matrix multiply on the cpu pool
+      let dev = self.device().clone();
+      let pool = self.device().pool().clone();
+      Box::new(ar.join(br).and_then(move |((shape_a, a), (shape_b, b))| {
+        pool.spawn_fn(move || {
+          let n_shape_a: &[usize] = try!(try!(shape_a.native_memory(&dev)).try_as_slice());
+          let n_a: &[T] = try!(try!(a.native_memory(&dev)).try_as_slice());
+          let n_shape_b: &[usize] = try!(try!(shape_b.native_memory(&dev)).try_as_slice());
+          let n_b: &[T] = try!(try!(b.native_memory(&dev)).try_as_slice());
+
+          if n_shape_a.len() != 2 || n_shape_b.len() != 2 || n_shape_a[1] != n_shape_b[0] {
+            return Err(Error::InvalidBroadcast)
+          }
+
+          let (m, k, n) = (n_shape_a[0], n_shape_a[1], n_shape_b[1]);
+          if n_a.len() != m * k || n_b.len() != k * n {
+            return Err(Error::InvalidBroadcast)
+          }
+
+          let mut c = try!(try!(Buffer::with_capacity_native(&dev, m * n)).try_lock());
+          let shape_c = try!(try!(Buffer::from_vec_native(&dev, vec![m, n])).try_lock());
+
+          {
+            let n_c: &mut [T] = try!(try!(c.native_memory_mut(&dev)).try_as_mut_slice());
+            T::gemm(m, n, k, n_a, n_b, n_c);
+          }
+
+          Ok((shape_c, c))
+        })
+      }))
+    }
+}
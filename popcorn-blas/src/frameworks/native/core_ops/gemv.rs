@@ -0,0 +1,78 @@
+use popcorn::frameworks::native::Framework;
+use popcorn::backend::Backend;
+use operation::*;
+use futures::Future;
+use popcorn::buffer::{Buffer, LockedBuffer, Error};
+#[cfg(not(feature = "pure"))]
+use blas_sys::c::{cblas_sgemv, Layout, Transpose};
+
+pub trait Gemv where Self: Sized {
+  // y = A·x for row major A (m x n), x (n) and y (m)
+  fn gemv(m: usize, n: usize, a: &[Self], x: &[Self], y: &mut [Self]);
+}
+
+#[cfg(not(feature = "pure"))]
+impl Gemv for f32 {
+  fn gemv(m: usize, n: usize, a: &[Self], x: &[Self], y: &mut [Self]) {
+    unsafe {
+      cblas_sgemv(Layout::RowMajor, Transpose::None,
+                  m as i32, n as i32,
+                  1.0, a.as_ptr(), n as i32,
+                  x.as_ptr(), 1,
+                  0.0, y.as_mut_ptr(), 1)
+    }
+  }
+}
+
+#[cfg(feature = "pure")]
+impl Gemv for f32 {
+  fn gemv(m: usize, n: usize, a: &[Self], x: &[Self], y: &mut [Self]) {
+    for (row, v) in a.chunks(n).take(m).zip(y.iter_mut()) {
+      *v = row.iter().zip(x.iter()).map(|(a, x)| a * x).sum();
+    }
+  }
+}
+
+impl<B: Backend<Framework>, T: Gemv + Sync + Copy + Sized + Send + 'static> GemvOperation<T> for B {
+  fn gemv(&self,
+          shape_a: LockedBuffer<usize>,
+          a: LockedBuffer<T>,
+          x: LockedBuffer<T>) ->
+    Box<Future<Item=(LockedBuffer<usize>, LockedBuffer<T>), Error=Error>> {
+      // Step 1. Sync all input buffers to the required device
+      let dev = self.device();
+      let ar = shape_a.sync(dev).join(a.sync(dev));
+      let xr = x.sync(dev);
+
+      // Step 2. Validate the shapes and execute the matrix-vector
+      //   product on the cpu pool
+      let dev = self.device().clone();
+      let pool = self.device().pool().clone();
+      Box::new(ar.join(xr).and_then(move |((shape_a, a), x)| {
+        pool.spawn_fn(move || {
+          let n_shape_a: &[usize] = try!(try!(shape_a.native_memory(&dev)).try_as_slice());
+          let n_a: &[T] = try!(try!(a.native_memory(&dev)).try_as_slice());
+          let n_x: &[T] = try!(try!(x.native_memory(&dev)).try_as_slice());
+
+          if n_shape_a.len() != 2 || n_shape_a[1] != n_x.len() {
+            return Err(Error::InvalidBroadcast)
+          }
+
+          let (m, n) = (n_shape_a[0], n_shape_a[1]);
+          if n_a.len() != m * n {
+            return Err(Error::InvalidBroadcast)
+          }
+
+          let mut y = try!(try!(Buffer::with_capacity_native(&dev, m)).try_lock());
+          let shape_y = try!(try!(Buffer::from_vec_native(&dev, vec![m])).try_lock());
+
+          {
+            let n_y: &mut [T] = try!(try!(y.native_memory_mut(&dev)).try_as_mut_slice());
+            T::gemv(m, n, n_a, n_x, n_y);
+          }
+
+          Ok((shape_y, y))
+        })
+      }))
+    }
+}
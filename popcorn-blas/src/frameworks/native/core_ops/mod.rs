@@ -1,3 +1,50 @@
+use popcorn::frameworks::native::Framework;
+use popcorn::backend::Backend;
+use futures::Future;
+use popcorn::buffer::{LockedBuffer, Error};
+
 pub mod dot;
+pub mod gemm;
+pub mod gemv;
+pub mod axpy;
+pub mod scal;
+pub mod norm;
+pub mod reduce;
+pub mod activation;
+pub mod softmax;
+pub mod scalar;
+pub mod clamp;
 
 pub use self::dot::*;
+pub use self::gemm::*;
+pub use self::gemv::*;
+pub use self::axpy::*;
+pub use self::scal::*;
+pub use self::norm::*;
+
+// Run `f` over all of x on the cpu pool, handing the buffer back along
+// with the result
+fn fold<B: Backend<Framework>,
+        T: Sync + Copy + Send + 'static,
+        R: Send + 'static>(backend: &B,
+                           x: LockedBuffer<T>,
+                           f: fn(&[T]) -> Result<R, Error>) ->
+  Box<Future<Item=(LockedBuffer<T>, R), Error=Error>> {
+    // Step 1. Sync the input buffer to the required device
+    let dev = backend.device();
+    let r = x.sync(dev);
+
+    // Step 2. Fold x on the cpu pool, handing the buffer back
+    let dev = backend.device().clone();
+    let pool = backend.device().pool().clone();
+    Box::new(r.and_then(move |x| {
+      pool.spawn_fn(move || {
+        let v = {
+          let n_x: &[T] = try!(try!(x.native_memory(&dev)).try_as_slice());
+          try!(f(n_x))
+        };
+
+        Ok((x, v))
+      })
+    }))
+  }
@@ -0,0 +1,54 @@
+use popcorn::frameworks::native::Framework;
+use popcorn::backend::Backend;
+use operation::*;
+use futures::Future;
+use popcorn::buffer::{LockedBuffer, Error};
+use super::fold;
+#[cfg(not(feature = "pure"))]
+use blas_sys::c::{cblas_snrm2, cblas_sasum};
+
+pub trait Norm where Self: Sized {
+  // Euclidean (L2) norm of x
+  fn nrm2(x: &[Self]) -> Self;
+
+  // Sum of absolute values (L1 norm) of x
+  fn asum(x: &[Self]) -> Self;
+}
+
+#[cfg(not(feature = "pure"))]
+impl Norm for f32 {
+  fn nrm2(x: &[Self]) -> Self {
+    unsafe {
+      cblas_snrm2(x.len() as i32, x.as_ptr(), 1)
+    }
+  }
+
+  fn asum(x: &[Self]) -> Self {
+    unsafe {
+      cblas_sasum(x.len() as i32, x.as_ptr(), 1)
+    }
+  }
+}
+
+#[cfg(feature = "pure")]
+impl Norm for f32 {
+  fn nrm2(x: &[Self]) -> Self {
+    x.iter().map(|v| v * v).sum::<f32>().sqrt()
+  }
+
+  fn asum(x: &[Self]) -> Self {
+    x.iter().map(|v| v.abs()).sum()
+  }
+}
+
+impl<B: Backend<Framework>, T: Norm + Sync + Copy + Sized + Send + 'static> NormOperation<T> for B {
+  fn nrm2(&self,
+          x: LockedBuffer<T>) -> Box<Future<Item=(LockedBuffer<T>, T), Error=Error>> {
+    fold(self, x, |x| Ok(T::nrm2(x)))
+  }
+
+  fn asum(&self,
+          x: LockedBuffer<T>) -> Box<Future<Item=(LockedBuffer<T>, T), Error=Error>> {
+    fold(self, x, |x| Ok(T::asum(x)))
+  }
+}
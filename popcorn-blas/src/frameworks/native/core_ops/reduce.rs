@@ -0,0 +1,113 @@
+use popcorn::frameworks::native::Framework;
+use popcorn::backend::Backend;
+use popcorn::num::Numeric;
+use operation::*;
+use futures::Future;
+use popcorn::buffer::{LockedBuffer, Error};
+use popcorn::cancel::CancelToken;
+use super::fold;
+
+// Elements summed between checks of a cancel token
+const CANCEL_CHUNK: usize = 1 << 16;
+
+fn sum<T: Numeric>(x: &[T]) -> Result<T, Error> {
+  Ok(x.iter().fold(T::zero(), |acc, v| acc + *v))
+}
+
+fn min<T: Numeric>(x: &[T]) -> Result<T, Error> {
+  let first = try!(x.first().ok_or(Error::EmptyBuffer));
+  Ok(x.iter().fold(*first, |m, v| if *v < m { *v } else { m }))
+}
+
+fn max<T: Numeric>(x: &[T]) -> Result<T, Error> {
+  let first = try!(x.first().ok_or(Error::EmptyBuffer));
+  Ok(x.iter().fold(*first, |m, v| if *v > m { *v } else { m }))
+}
+
+fn mean<T: Numeric>(x: &[T]) -> Result<T, Error> {
+  if x.is_empty() {
+    return Err(Error::EmptyBuffer)
+  }
+
+  Ok(try!(sum(x)) / T::from_usize(x.len()))
+}
+
+// Index of the first element that `better` prefers over all others
+fn arg_by<T: PartialOrd>(x: &[T], better: fn(&T, &T) -> bool) -> Result<usize, Error> {
+  if x.is_empty() {
+    return Err(Error::EmptyBuffer)
+  }
+
+  Ok((1..x.len()).fold(0, |best, i| if better(&x[i], &x[best]) { i } else { best }))
+}
+
+fn argmax<T: PartialOrd>(x: &[T]) -> Result<usize, Error> {
+  arg_by(x, |a, b| a > b)
+}
+
+fn argmin<T: PartialOrd>(x: &[T]) -> Result<usize, Error> {
+  arg_by(x, |a, b| a < b)
+}
+
+impl<B: Backend<Framework>, T: Numeric + Sync + Send + 'static> ReduceOperation<T> for B {
+  fn sum(&self,
+         x: LockedBuffer<T>) -> Box<Future<Item=(LockedBuffer<T>, T), Error=Error>> {
+    fold(self, x, sum)
+  }
+
+  fn min(&self,
+         x: LockedBuffer<T>) -> Box<Future<Item=(LockedBuffer<T>, T), Error=Error>> {
+    fold(self, x, min)
+  }
+
+  fn max(&self,
+         x: LockedBuffer<T>) -> Box<Future<Item=(LockedBuffer<T>, T), Error=Error>> {
+    fold(self, x, max)
+  }
+
+  fn mean(&self,
+          x: LockedBuffer<T>) -> Box<Future<Item=(LockedBuffer<T>, T), Error=Error>> {
+    fold(self, x, mean)
+  }
+
+  fn sum_cancellable(&self,
+                     x: LockedBuffer<T>,
+                     token: CancelToken) -> Box<Future<Item=(LockedBuffer<T>, T), Error=Error>> {
+    // Step 1. Sync the input buffer to the required device
+    let dev = self.device();
+    let r = x.sync(dev);
+
+    // Step 2. Sum x on the cpu pool a chunk at a time, stopping at
+    //   the first chunk after the token is cancelled
+    let dev = self.device().clone();
+    let pool = self.device().pool().clone();
+    let kernel_token = token.clone();
+    Box::new(token.guard(r.and_then(move |x| {
+      pool.spawn_fn(move || {
+        let v = {
+          let n_x: &[T] = try!(try!(x.native_memory(&dev)).try_as_slice());
+          let mut acc = T::zero();
+          for chunk in n_x.chunks(CANCEL_CHUNK) {
+            try!(kernel_token.check());
+            acc = chunk.iter().fold(acc, |acc, v| acc + *v);
+          }
+          acc
+        };
+
+        Ok((x, v))
+      })
+    })))
+  }
+}
+
+impl<B: Backend<Framework>, T: PartialOrd + Sync + Copy + Send + 'static> ArgReduceOperation<T> for B {
+  fn argmax(&self,
+            x: LockedBuffer<T>) -> Box<Future<Item=(LockedBuffer<T>, usize), Error=Error>> {
+    fold(self, x, argmax)
+  }
+
+  fn argmin(&self,
+            x: LockedBuffer<T>) -> Box<Future<Item=(LockedBuffer<T>, usize), Error=Error>> {
+    fold(self, x, argmin)
+  }
+}
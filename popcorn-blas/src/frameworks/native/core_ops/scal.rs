@@ -0,0 +1,57 @@
+use popcorn::frameworks::native::Framework;
+use popcorn::backend::Backend;
+use operation::*;
+use futures::Future;
+use popcorn::buffer::{LockedBuffer, Error};
+#[cfg(not(feature = "pure"))]
+use blas_sys::c::cblas_sscal;
+
+pub trait Scal where Self: Sized {
+  // x = alpha * x
+  fn scal(alpha: Self, x: &mut [Self]);
+}
+
+#[cfg(not(feature = "pure"))]
+impl Scal for f32 {
+  fn scal(alpha: Self, x: &mut [Self]) {
+    unsafe {
+      cblas_sscal(x.len() as i32, alpha, x.as_mut_ptr(), 1)
+    }
+  }
+}
+
+#[cfg(feature = "pure")]
+impl Scal for f32 {
+  fn scal(alpha: Self, x: &mut [Self]) {
+    for v in x.iter_mut() {
+      *v = alpha * *v;
+    }
+  }
+}
+
+impl<B: Backend<Framework>, T: Scal + Sync + Copy + Sized + Send + 'static> ScalOperation<T> for B {
+  fn scal(&self,
+          alpha: T,
+          x: LockedBuffer<T>) -> Box<Future<Item=LockedBuffer<T>, Error=Error>> {
+    // Step 1. Sync the input buffer to the required device
+    let dev = self.device();
+    let r = x.sync(dev);
+
+    // Step 2. Scale x in place on the cpu pool, which also makes
+    //   this device hold the latest copy
+    let dev = self.device().clone();
+    let pool = self.device().pool().clone();
+    Box::new(r.and_then(move |mut x| {
+      pool.spawn_fn(move || {
+        {
+          let n_x: &mut [T] = try!(try!(x.native_memory_mut(&dev)).try_as_mut_slice());
+          if !n_x.is_empty() {
+            T::scal(alpha, n_x);
+          }
+        }
+
+        Ok(x)
+      })
+    }))
+  }
+}
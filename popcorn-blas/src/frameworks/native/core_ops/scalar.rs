@@ -0,0 +1,64 @@
+use popcorn::frameworks::native::Framework;
+use popcorn::backend::Backend;
+use popcorn::num::Numeric;
+use operation::*;
+use frameworks::native::par;
+use futures::{Future, IntoFuture};
+use popcorn::buffer::{LockedBuffer, Error};
+
+fn update<B: Backend<Framework>,
+          T: Numeric + Sync + Send + 'static>(backend: &B,
+                                              x: LockedBuffer<T>,
+                                              alpha: T,
+                                              f: fn(T, T) -> T) ->
+  Box<Future<Item=LockedBuffer<T>, Error=Error>> {
+    // Step 1. Sync the input buffer to the required device
+    let dev = backend.device();
+    let r = x.sync(dev);
+
+    // Step 2. Update x in place on the cpu pool, which also makes
+    //   this device hold the latest copy
+    let dev = backend.device().clone();
+    let pool = backend.device().pool().clone();
+    Box::new(r.and_then(move |mut x| {
+      pool.spawn_fn(move || {
+        {
+          let n_x: &mut [T] = try!(try!(x.native_memory_mut(&dev)).try_as_mut_slice());
+          par::update(n_x, |v| *v = f(*v, alpha));
+        }
+
+        Ok(x)
+      })
+    }))
+  }
+
+impl<B: Backend<Framework>, T: Numeric + Sync + Send + 'static> ScalarOperation<T> for B {
+  fn add_scalar(&self,
+                x: LockedBuffer<T>,
+                alpha: T) -> Box<Future<Item=LockedBuffer<T>, Error=Error>> {
+    update(self, x, alpha, |v, a| v + a)
+  }
+
+  fn sub_scalar(&self,
+                x: LockedBuffer<T>,
+                alpha: T) -> Box<Future<Item=LockedBuffer<T>, Error=Error>> {
+    update(self, x, alpha, |v, a| v - a)
+  }
+
+  fn mul_scalar(&self,
+                x: LockedBuffer<T>,
+                alpha: T) -> Box<Future<Item=LockedBuffer<T>, Error=Error>> {
+    update(self, x, alpha, |v, a| v * a)
+  }
+
+  fn div_scalar(&self,
+                x: LockedBuffer<T>,
+                alpha: T) -> Box<Future<Item=LockedBuffer<T>, Error=Error>> {
+    // Only integers can fail, and then only on a zero divisor
+    if T::one().checked_div(alpha).is_none() {
+      return Box::new(Err(Error::DivisionByZero).into_future())
+    }
+
+    update(self, x, alpha, |v, a| v / a)
+  }
+}
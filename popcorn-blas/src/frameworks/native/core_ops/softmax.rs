@@ -0,0 +1,77 @@
+use popcorn::frameworks::native::Framework;
+use popcorn::backend::Backend;
+use popcorn::num::Float;
+use operation::*;
+use futures::{Future, IntoFuture};
+use popcorn::buffer::{Buffer, LockedBuffer, Error};
+
+// The max is subtracted before exponentiating so large inputs can't
+// overflow, which leaves the result unchanged
+fn softmax<T: Float>(x: &[T], y: &mut [T]) {
+  let max = match x.first() {
+    Some(first) => x.iter().fold(*first, |m, v| if *v > m { *v } else { m }),
+    None => return
+  };
+
+  let mut sum = T::zero();
+  for (v, x) in y.iter_mut().zip(x.iter()) {
+    *v = (*x - max).exp();
+    sum = sum + *v;
+  }
+
+  for v in y.iter_mut() {
+    *v = *v / sum;
+  }
+}
+
+impl<B: Backend<Framework>, T: Float + Sync + Send + 'static> SoftmaxOperation<T> for B {
+  fn softmax(&self,
+             x: LockedBuffer<T>) -> Box<Future<Item=LockedBuffer<T>, Error=Error>> {
+    let dev = self.device();
+    let shape = match Buffer::from_vec_native(dev, vec![x.size()]).and_then(|s| s.try_lock()) {
+      Ok(shape) => shape,
+      Err(err) => return Box::new(Err(err).into_future())
+    };
+
+    Box::new(self.softmax_last_axis(shape, x).map(|(_, y)| y))
+  }
+
+  fn softmax_last_axis(&self,
+                       shape: LockedBuffer<usize>,
+                       x: LockedBuffer<T>) ->
+    Box<Future<Item=(LockedBuffer<usize>, LockedBuffer<T>), Error=Error>> {
+      // Step 1. Sync all input buffers to the required device
+      let dev = self.device();
+      let r = shape.sync(dev).join(x.sync(dev));
+
+      // Step 2. Validate the shape and normalize each run of the last
+      //   axis on the cpu pool
+      let dev = self.device().clone();
+      let pool = self.device().pool().clone();
+      Box::new(r.and_then(move |(shape, x)| {
+        pool.spawn_fn(move || {
+          let mut y = try!(try!(Buffer::with_capacity_native(&dev, x.size())).try_lock());
+
+          {
+            let n_shape: &[usize] = try!(try!(shape.native_memory(&dev)).try_as_slice());
+            let n_x: &[T] = try!(try!(x.native_memory(&dev)).try_as_slice());
+            let n_y: &mut [T] = try!(try!(y.native_memory_mut(&dev)).try_as_mut_slice());
+
+            let len = n_shape.iter().product();
+            if n_x.len() != len {
+              return Err(Error::SizeMismatch { expected: len, actual: n_x.len() })
+            }
+
+            let axis = n_shape.last().cloned().unwrap_or(1);
+            if axis != 0 {
+              for (x, y) in n_x.chunks(axis).zip(n_y.chunks_mut(axis)) {
+                softmax(x, y);
+              }
+            }
+          }
+
+          Ok((shape, y))
+        })
+      }))
+    }
+}
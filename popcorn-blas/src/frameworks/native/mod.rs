@@ -1,4 +1,5 @@
 pub mod broadcast;
 pub mod core_ops;
+mod par;
 
 pub use self::core_ops::*;
@@ -0,0 +1,43 @@
+//! Loops over the elements of native memory. With the `rayon` feature
+//! they are split into chunks run on rayon's threads, otherwise they
+//! run serially on the calling worker.
+//!
+//! Only the elementwise kernels go through here. `bcast_dot` already
+//! splits its rows across the device pool, and the BLAS backed and
+//! reducing kernels run as a single task either way.
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+// Elements handed to a rayon task at a time, small buffers are left
+// in a single chunk
+#[cfg(feature = "rayon")]
+const CHUNK_SIZE: usize = 4096;
+
+/// Apply `f` to every element of `xs` in place.
+#[cfg(feature = "rayon")]
+pub fn update<T: Send, F: Fn(&mut T) + Sync + Send>(xs: &mut [T], f: F) {
+  xs.par_chunks_mut(CHUNK_SIZE).for_each(|c| c.iter_mut().for_each(&f));
+}
+
+#[cfg(not(feature = "rayon"))]
+pub fn update<T: Send, F: Fn(&mut T) + Sync + Send>(xs: &mut [T], f: F) {
+  xs.iter_mut().for_each(f);
+}
+
+/// Set every element of `ys` to `f` of the matching element of `xs`.
+#[cfg(feature = "rayon")]
+pub fn map<T: Sync, U: Send, F: Fn(&T) -> U + Sync + Send>(xs: &[T], ys: &mut [U], f: F) {
+  ys.par_chunks_mut(CHUNK_SIZE).zip(xs.par_chunks(CHUNK_SIZE)).for_each(|(ys, xs)| {
+    for (y, x) in ys.iter_mut().zip(xs.iter()) {
+      *y = f(x);
+    }
+  });
+}
+
+#[cfg(not(feature = "rayon"))]
+pub fn map<T: Sync, U: Send, F: Fn(&T) -> U + Sync + Send>(xs: &[T], ys: &mut [U], f: F) {
+  for (y, x) in ys.iter_mut().zip(xs.iter()) {
+    *y = f(x);
+  }
+}
@@ -1,12 +1,21 @@
 extern crate futures;
 extern crate popcorn;
+#[cfg(not(feature = "pure"))]
 extern crate blas_sys;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(feature = "complex")]
+extern crate num_complex;
 
 pub mod operation;
 pub mod frameworks;
+pub mod tensor;
 
 pub use operation::*;
+pub use tensor::{Tensor, LockedTensor};
 pub use frameworks::native::*;
+#[cfg(feature = "complex")]
+pub use num_complex::Complex;
 
 #[cfg(test)]
 mod native_test {
@@ -34,4 +43,511 @@ mod native_test {
     println!("Shape: {:?}", &shape_c_vec);
     println!("Contents: {:?}", &c_vec);
   }
+
+  #[test]
+  fn dot_f64_test() {
+    let backend = popcorn::frameworks::native::Backend::default();
+
+    let shape_a: LockedBuffer<usize> = Buffer::from_vec_native(backend.device(), vec![1, 4]).unwrap().try_lock().unwrap();
+    let a: LockedBuffer<f64> = Buffer::from_vec_native(backend.device(), vec![1.0, 2.0, 3.0, 4.0]).unwrap().try_lock().unwrap();
+    let shape_b: LockedBuffer<usize> = Buffer::from_vec_native(backend.device(), vec![1, 4]).unwrap().try_lock().unwrap();
+    let b: LockedBuffer<f64> = Buffer::from_vec_native(backend.device(), vec![0.5, 0.5, 0.5, 0.5]).unwrap().try_lock().unwrap();
+
+    let (shape_c, c) = backend.bcast_dot(shape_a, a,
+                                         shape_b, b).wait().unwrap();
+
+    assert_eq!(shape_c.sync_to_vec().wait().unwrap(), vec![1]);
+    assert_eq!(c.sync_to_vec().wait().unwrap(), vec![5.0]);
+  }
+
+  #[test]
+  fn gemm_test() {
+    let backend = popcorn::frameworks::native::Backend::default();
+
+    let shape_a: LockedBuffer<usize> = Buffer::from_vec_native(backend.device(), vec![2, 3]).unwrap().try_lock().unwrap();
+    let a: LockedBuffer<f32> = Buffer::from_vec_native(backend.device(), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap().try_lock().unwrap();
+    let shape_b: LockedBuffer<usize> = Buffer::from_vec_native(backend.device(), vec![3, 2]).unwrap().try_lock().unwrap();
+    let b: LockedBuffer<f32> = Buffer::from_vec_native(backend.device(), vec![7.0, 8.0, 9.0, 10.0, 11.0, 12.0]).unwrap().try_lock().unwrap();
+
+    let (shape_c, c) = backend.bcast_gemm(shape_a, a,
+                                          shape_b, b).wait().unwrap();
+
+    assert_eq!(shape_c.sync_to_vec().wait().unwrap(), vec![2, 2]);
+    assert_eq!(c.sync_to_vec().wait().unwrap(), vec![58.0, 64.0, 139.0, 154.0]);
+  }
+
+  #[test]
+  fn axpy_test() {
+    let backend = popcorn::frameworks::native::Backend::default();
+
+    let x: LockedBuffer<f32> = Buffer::from_vec_native(backend.device(), vec![1.0, 1.0]).unwrap().try_lock().unwrap();
+    let y: LockedBuffer<f32> = Buffer::from_vec_native(backend.device(), vec![3.0, 3.0]).unwrap().try_lock().unwrap();
+
+    let y = backend.axpy(2.0, x, y).wait().unwrap();
+    assert_eq!(y.sync_to_vec().wait().unwrap(), vec![5.0, 5.0]);
+  }
+
+  #[test]
+  fn scal_test() {
+    let backend = popcorn::frameworks::native::Backend::default();
+
+    let x: LockedBuffer<f32> = Buffer::from_vec_native(backend.device(), vec![1.0, 2.0, 3.0]).unwrap().try_lock().unwrap();
+    let x = backend.scal(0.5, x).wait().unwrap();
+    assert_eq!(x.sync_to_vec().wait().unwrap(), vec![0.5, 1.0, 1.5]);
+
+    let empty: LockedBuffer<f32> = Buffer::new(backend.device(), 0).unwrap().try_lock().unwrap();
+    let empty = backend.scal(0.5, empty).wait().unwrap();
+    assert_eq!(empty.size(), 0);
+  }
+
+  #[test]
+  fn norm_test() {
+    let backend = popcorn::frameworks::native::Backend::default();
+
+    let x: LockedBuffer<f32> = Buffer::from_vec_native(backend.device(), vec![3.0, -4.0]).unwrap().try_lock().unwrap();
+    let (x, nrm2) = backend.nrm2(x).wait().unwrap();
+    assert!((nrm2 - 5.0).abs() < 1e-6);
+
+    let (_, asum) = backend.asum(x).wait().unwrap();
+    assert!((asum - 7.0).abs() < 1e-6);
+  }
+
+  #[test]
+  fn gemv_test() {
+    let backend = popcorn::frameworks::native::Backend::default();
+
+    let shape_a: LockedBuffer<usize> = Buffer::from_vec_native(backend.device(), vec![2, 3]).unwrap().try_lock().unwrap();
+    let a: LockedBuffer<f32> = Buffer::from_vec_native(backend.device(), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap().try_lock().unwrap();
+    let x: LockedBuffer<f32> = Buffer::from_vec_native(backend.device(), vec![1.0, 0.0, 2.0]).unwrap().try_lock().unwrap();
+
+    let (shape_y, y) = backend.gemv(shape_a, a, x).wait().unwrap();
+    assert_eq!(shape_y.sync_to_vec().wait().unwrap(), vec![2]);
+    assert_eq!(y.sync_to_vec().wait().unwrap(), vec![7.0, 16.0]);
+  }
+
+  #[test]
+  fn gemv_mismatch_test() {
+    let backend = popcorn::frameworks::native::Backend::default();
+
+    let shape_a: LockedBuffer<usize> = Buffer::from_vec_native(backend.device(), vec![2, 3]).unwrap().try_lock().unwrap();
+    let a: LockedBuffer<f32> = Buffer::from_vec_native(backend.device(), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap().try_lock().unwrap();
+    let x: LockedBuffer<f32> = Buffer::from_vec_native(backend.device(), vec![1.0, 0.0]).unwrap().try_lock().unwrap();
+
+    assert!(backend.gemv(shape_a, a, x).wait().is_err());
+  }
+
+  #[test]
+  fn tensor_test() {
+    let backend = popcorn::frameworks::native::Backend::default();
+
+    let t = Tensor::new_native(backend.device(), vec![2, 3], vec![1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+    assert_eq!(t.shape(), &[2, 3]);
+    assert_eq!(t.len(), 6);
+
+    let locked = t.lock().wait().unwrap();
+    assert_eq!(locked.shape(), &[2, 3]);
+    assert_eq!(locked.len(), 6);
+
+    let (shape, data) = locked.into_buffers().unwrap();
+    assert_eq!(shape.sync_to_vec().wait().unwrap(), vec![2, 3]);
+    assert_eq!(data.sync_to_vec().wait().unwrap(), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+
+    assert!(Tensor::new_native(backend.device(), vec![2, 2], vec![1.0f32, 2.0, 3.0]).is_err());
+  }
+
+  #[test]
+  fn tensor_broadcast_ops_test() {
+    let backend = popcorn::frameworks::native::Backend::default();
+
+    let a = Tensor::new_native(backend.device(), vec![2, 3], vec![1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+    let b = Tensor::new_native(backend.device(), vec![3], vec![10.0f32, 20.0, 30.0]).unwrap();
+
+    let c = (&a + &b).wait().unwrap();
+    assert_eq!(c.shape(), &[2, 3]);
+    assert_eq!(c.data().lock().and_then(|c| c.sync_to_vec()).wait().unwrap(),
+               vec![11.0, 22.0, 33.0, 14.0, 25.0, 36.0]);
+
+    let d = (&a - &b).wait().unwrap();
+    assert_eq!(d.data().lock().and_then(|d| d.sync_to_vec()).wait().unwrap(),
+               vec![-9.0, -18.0, -27.0, -6.0, -15.0, -24.0]);
+
+    // The same tensor on both sides
+    let e = (&a * &a).wait().unwrap();
+    assert_eq!(e.data().lock().and_then(|e| e.sync_to_vec()).wait().unwrap(),
+               vec![1.0, 4.0, 9.0, 16.0, 25.0, 36.0]);
+
+    let bad = Tensor::new_native(backend.device(), vec![2], vec![1.0f32, 2.0]).unwrap();
+    assert!((&a + &bad).wait().is_err());
+  }
+
+  #[test]
+  fn tensor_matmul_test() {
+    let backend = popcorn::frameworks::native::Backend::default();
+
+    let a = Tensor::new_native(backend.device(), vec![2, 3], vec![1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+    let b = Tensor::new_native(backend.device(), vec![3, 2], vec![7.0f32, 8.0, 9.0, 10.0, 11.0, 12.0]).unwrap();
+
+    let c = a.matmul(&b).wait().unwrap();
+    assert_eq!(c.shape(), &[2, 2]);
+    assert_eq!(c.data().lock().and_then(|c| c.sync_to_vec()).wait().unwrap(), vec![58.0, 64.0, 139.0, 154.0]);
+
+    assert!(a.matmul(&a).wait().is_err());
+  }
+
+  #[test]
+  fn tensor_eye_test() {
+    let backend = popcorn::frameworks::native::Backend::default();
+
+    let eye = Tensor::<f32>::eye_native(backend.device(), 3).wait().unwrap();
+    assert_eq!(eye.shape(), &[3, 3]);
+    assert_eq!(eye.data().lock().and_then(|e| e.sync_to_vec()).wait().unwrap(),
+               vec![1.0, 0.0, 0.0,
+                    0.0, 1.0, 0.0,
+                    0.0, 0.0, 1.0]);
+
+    let a = Tensor::new_native(backend.device(), vec![3, 2], vec![1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+    let c = eye.matmul(&a).wait().unwrap();
+    assert_eq!(c.data().lock().and_then(|c| c.sync_to_vec()).wait().unwrap(), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+
+    let empty = Tensor::<f32>::eye_native(backend.device(), 0).wait().unwrap();
+    assert_eq!(empty.len(), 0);
+  }
+
+  #[test]
+  fn reduce_test() {
+    let backend = popcorn::frameworks::native::Backend::default();
+
+    let x: LockedBuffer<f32> = Buffer::from_vec_native(backend.device(), vec![3.0, -1.0, 4.0, 2.0]).unwrap().try_lock().unwrap();
+    let (x, sum) = backend.sum(x).wait().unwrap();
+    assert_eq!(sum, 8.0);
+    let (x, min) = backend.min(x).wait().unwrap();
+    assert_eq!(min, -1.0);
+    let (x, max) = backend.max(x).wait().unwrap();
+    assert_eq!(max, 4.0);
+    let (_, mean) = backend.mean(x).wait().unwrap();
+    assert_eq!(mean, 2.0);
+
+    let x: LockedBuffer<i32> = Buffer::from_vec_native(backend.device(), vec![1, 2, 3]).unwrap().try_lock().unwrap();
+    let (_, sum) = backend.sum(x).wait().unwrap();
+    assert_eq!(sum, 6);
+  }
+
+  #[test]
+  fn reduce_empty_test() {
+    let backend = popcorn::frameworks::native::Backend::default();
+
+    let empty: LockedBuffer<f64> = Buffer::new(backend.device(), 0).unwrap().try_lock().unwrap();
+    let (empty, sum) = backend.sum(empty).wait().unwrap();
+    assert_eq!(sum, 0.0);
+
+    assert!(match backend.min(empty).wait() { Err(popcorn::buffer::Error::EmptyBuffer) => true, _ => false });
+
+    let empty: LockedBuffer<f64> = Buffer::new(backend.device(), 0).unwrap().try_lock().unwrap();
+    assert!(match backend.max(empty).wait() { Err(popcorn::buffer::Error::EmptyBuffer) => true, _ => false });
+  }
+
+  #[test]
+  fn argmax_test() {
+    let backend = popcorn::frameworks::native::Backend::default();
+
+    let x: LockedBuffer<f32> = Buffer::from_vec_native(backend.device(), vec![1.0, 5.0, 5.0, 2.0]).unwrap().try_lock().unwrap();
+    let (x, i) = backend.argmax(x).wait().unwrap();
+    assert_eq!(i, 1);
+    let (_, i) = backend.argmin(x).wait().unwrap();
+    assert_eq!(i, 0);
+
+    let x: LockedBuffer<i32> = Buffer::from_vec_native(backend.device(), vec![4, 1, 1, 9]).unwrap().try_lock().unwrap();
+    let (_, i) = backend.argmin(x).wait().unwrap();
+    assert_eq!(i, 1);
+
+    let empty: LockedBuffer<f32> = Buffer::new(backend.device(), 0).unwrap().try_lock().unwrap();
+    assert!(match backend.argmax(empty).wait() { Err(popcorn::buffer::Error::EmptyBuffer) => true, _ => false });
+  }
+
+  #[test]
+  fn activation_test() {
+    let backend = popcorn::frameworks::native::Backend::default();
+
+    let x: LockedBuffer<f32> = Buffer::from_vec_native(backend.device(), vec![-1.0, 0.0, 2.0]).unwrap().try_lock().unwrap();
+    let y = backend.relu(x).wait().unwrap();
+    assert_eq!(y.sync_to_vec().wait().unwrap(), vec![0.0, 0.0, 2.0]);
+
+    let x: LockedBuffer<f64> = Buffer::from_vec_native(backend.device(), vec![0.0, 100.0, -100.0]).unwrap().try_lock().unwrap();
+    let y = backend.sigmoid(x).wait().unwrap().sync_to_vec().wait().unwrap();
+    assert_eq!(y[0], 0.5);
+    assert!((y[1] - 1.0).abs() < 1e-12);
+    assert!(y[2].abs() < 1e-12);
+
+    let x: LockedBuffer<f64> = Buffer::from_vec_native(backend.device(), vec![0.0, 1.0]).unwrap().try_lock().unwrap();
+    let y = backend.tanh(x).wait().unwrap().sync_to_vec().wait().unwrap();
+    assert_eq!(y, vec![0.0, 1.0f64.tanh()]);
+  }
+
+  #[test]
+  fn softmax_test() {
+    let backend = popcorn::frameworks::native::Backend::default();
+
+    let x: LockedBuffer<f32> = Buffer::from_vec_native(backend.device(), vec![1.0, 2.0, 3.0, 4.0]).unwrap().try_lock().unwrap();
+    let y = backend.softmax(x).wait().unwrap().sync_to_vec().wait().unwrap();
+    assert!((y.iter().sum::<f32>() - 1.0).abs() < 1e-6);
+    assert!(y.windows(2).all(|w| w[0] < w[1]));
+
+    // Would overflow without subtracting the max first
+    let x: LockedBuffer<f32> = Buffer::from_vec_native(backend.device(), vec![1000.0, 1001.0]).unwrap().try_lock().unwrap();
+    let y = backend.softmax(x).wait().unwrap().sync_to_vec().wait().unwrap();
+    assert!((y.iter().sum::<f32>() - 1.0).abs() < 1e-6);
+    assert!(y[0] < y[1]);
+
+    let x: LockedBuffer<f64> = Buffer::from_vec_native(backend.device(), vec![-3.0]).unwrap().try_lock().unwrap();
+    assert_eq!(backend.softmax(x).wait().unwrap().sync_to_vec().wait().unwrap(), vec![1.0]);
+
+    let shape: LockedBuffer<usize> = Buffer::from_vec_native(backend.device(), vec![2, 2]).unwrap().try_lock().unwrap();
+    let x: LockedBuffer<f64> = Buffer::from_vec_native(backend.device(), vec![0.0, 0.0, 5.0, 5.0]).unwrap().try_lock().unwrap();
+    let (shape, y) = backend.softmax_last_axis(shape, x).wait().unwrap();
+    assert_eq!(shape.sync_to_vec().wait().unwrap(), vec![2, 2]);
+    assert_eq!(y.sync_to_vec().wait().unwrap(), vec![0.5, 0.5, 0.5, 0.5]);
+  }
+
+  #[test]
+  fn scalar_test() {
+    let backend = popcorn::frameworks::native::Backend::default();
+
+    let x: LockedBuffer<f32> = Buffer::from_vec_native(backend.device(), vec![1.0, 2.0, 3.0]).unwrap().try_lock().unwrap();
+    let x = backend.mul_scalar(x, 10.0).wait().unwrap();
+    let x = backend.add_scalar(x, 1.0).wait().unwrap();
+    let x = backend.sub_scalar(x, 3.0).wait().unwrap();
+    let x = backend.div_scalar(x, 2.0).wait().unwrap();
+    assert_eq!(x.sync_to_vec().wait().unwrap(), vec![4.0, 9.0, 14.0]);
+
+    let x: LockedBuffer<f64> = Buffer::from_vec_native(backend.device(), vec![1.0, -1.0, 0.0]).unwrap().try_lock().unwrap();
+    let y = backend.div_scalar(x, 0.0).wait().unwrap().sync_to_vec().wait().unwrap();
+    assert_eq!(y[0], f64::INFINITY);
+    assert_eq!(y[1], f64::NEG_INFINITY);
+    assert!(y[2].is_nan());
+
+    let x: LockedBuffer<i32> = Buffer::from_vec_native(backend.device(), vec![1, 2, 3]).unwrap().try_lock().unwrap();
+    assert!(match backend.div_scalar(x, 0).wait() { Err(popcorn::buffer::Error::DivisionByZero) => true, _ => false });
+  }
+
+  #[test]
+  fn clamp_test() {
+    let backend = popcorn::frameworks::native::Backend::default();
+
+    let x: LockedBuffer<i32> = Buffer::from_vec_native(backend.device(), vec![-5, 0, 5, 10]).unwrap().try_lock().unwrap();
+    let x = backend.clamp(x, 0, 6).wait().unwrap();
+    assert_eq!(x.sync_to_vec().wait().unwrap(), vec![0, 0, 5, 6]);
+
+    let x: LockedBuffer<f32> = Buffer::from_vec_native(backend.device(), vec![1.0]).unwrap().try_lock().unwrap();
+    assert!(match backend.clamp(x, 1.0, 0.0).wait() { Err(popcorn::buffer::Error::InvalidRange) => true, _ => false });
+  }
+
+  #[test]
+  fn tensor_reshape_test() {
+    let backend = popcorn::frameworks::native::Backend::default();
+    let t = || Tensor::new_native(backend.device(), vec![2, 3], vec![1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+
+    let r = t().reshape(vec![3, 2]).unwrap();
+    assert_eq!(r.shape(), &[3, 2]);
+    assert_eq!(r.data().lock().and_then(|d| d.sync_to_vec()).wait().unwrap(), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+    assert!(match t().reshape(vec![4, 2]) {
+      Err(popcorn::buffer::Error::SizeMismatch { expected: 6, actual: 8 }) => true,
+      _ => false
+    });
+
+    assert_eq!(t().reshape_infer(&[-1, 2]).unwrap().shape(), &[3, 2]);
+    assert_eq!(t().reshape_infer(&[6, -1]).unwrap().shape(), &[6, 1]);
+    assert!(t().reshape_infer(&[-1, -1]).is_err());
+    assert!(t().reshape_infer(&[-1, 4]).is_err());
+  }
+
+  #[test]
+  fn tensor_transpose_test() {
+    let backend = popcorn::frameworks::native::Backend::default();
+
+    let a = Tensor::new_native(backend.device(), vec![2, 3], vec![1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+    let t = a.transpose().wait().unwrap();
+    assert_eq!(t.shape(), &[3, 2]);
+    assert_eq!(t.data().lock().and_then(|d| d.sync_to_vec()).wait().unwrap(), vec![1.0, 4.0, 2.0, 5.0, 3.0, 6.0]);
+
+    let v = Tensor::new_native(backend.device(), vec![3], vec![1.0f32, 2.0, 3.0]).unwrap();
+    assert!(match v.transpose().wait() { Err(popcorn::buffer::Error::InvalidShape) => true, _ => false });
+  }
+
+  #[test]
+  fn dot_parallel_test() {
+    let serial = popcorn::frameworks::native::Backend::with_threads(1).unwrap();
+    let parallel = popcorn::frameworks::native::Backend::with_threads(4).unwrap();
+
+    // 1000 rows of 37 broadcast against a single row, enough for
+    // several chunks on the parallel backend
+    let a: Vec<f64> = (0..37000).map(|i| ((i * 7919) % 1000) as f64 / 997.0).collect();
+    let b: Vec<f64> = (0..37).map(|i| 1.0 / (i + 1) as f64).collect();
+
+    let run = |backend: &popcorn::frameworks::native::Backend| {
+      let dev = backend.device();
+      let shape_a: LockedBuffer<usize> = Buffer::from_vec_native(dev, vec![1000, 37]).unwrap().try_lock().unwrap();
+      let a: LockedBuffer<f64> = Buffer::from_vec_native(dev, a.clone()).unwrap().try_lock().unwrap();
+      let shape_b: LockedBuffer<usize> = Buffer::from_vec_native(dev, vec![37]).unwrap().try_lock().unwrap();
+      let b: LockedBuffer<f64> = Buffer::from_vec_native(dev, b.clone()).unwrap().try_lock().unwrap();
+
+      let (shape_c, c) = backend.bcast_dot(shape_a, a, shape_b, b).wait().unwrap();
+      (shape_c.sync_to_vec().wait().unwrap(), c.sync_to_vec().wait().unwrap())
+    };
+
+    let (shape_s, c_s) = run(&serial);
+    let (shape_p, c_p) = run(&parallel);
+    assert_eq!(shape_s, vec![1000]);
+    assert_eq!(shape_p, shape_s);
+    assert_eq!(c_p, c_s);
+    assert!((c_s[999] - a[999 * 37..].iter().zip(b.iter()).map(|(x, y)| x * y).sum::<f64>()).abs() < 1e-9);
+  }
+
+  #[test]
+  #[cfg(feature = "pure")]
+  fn pure_dot_test() {
+    let backend = popcorn::frameworks::native::Backend::default();
+
+    // Small integers are exact in floating point, so these match what
+    // any BLAS computes
+    let xs: Vec<f64> = (1..101).map(|i| i as f64).collect();
+    assert_eq!(<f64 as Dot>::dot(&xs, &xs), 338350.0);
+
+    let shape_a: LockedBuffer<usize> = Buffer::from_vec_native(backend.device(), vec![2, 3]).unwrap().try_lock().unwrap();
+    let a: LockedBuffer<f32> = Buffer::from_vec_native(backend.device(), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap().try_lock().unwrap();
+    let shape_b: LockedBuffer<usize> = Buffer::from_vec_native(backend.device(), vec![3]).unwrap().try_lock().unwrap();
+    let b: LockedBuffer<f32> = Buffer::from_vec_native(backend.device(), vec![1.0, 0.0, -1.0]).unwrap().try_lock().unwrap();
+
+    let (shape_c, c) = backend.bcast_dot(shape_a, a, shape_b, b).wait().unwrap();
+    assert_eq!(shape_c.sync_to_vec().wait().unwrap(), vec![2]);
+    assert_eq!(c.sync_to_vec().wait().unwrap(), vec![-2.0, -2.0]);
+  }
+
+  #[test]
+  fn par_large_buffer() {
+    let backend = popcorn::frameworks::native::Backend::default();
+    let dev = backend.device();
+
+    // Large enough to be split up when built with rayon
+    let xs: Vec<f64> = (0..100_003).map(|i| (i % 17) as f64 - 8.0).collect();
+    let x = Buffer::from_vec_native(dev, xs.clone()).unwrap().lock().wait().unwrap();
+
+    let x = backend.mul_scalar(x, 0.5).and_then(|x| backend.clamp(x, -3.0, 3.0)).wait().unwrap();
+    let y = backend.relu(x).and_then(|y| y.sync_to_vec()).wait().unwrap();
+
+    let expected: Vec<f64> = xs.iter().map(|x| (x * 0.5).max(-3.0).min(3.0).max(0.0)).collect();
+    assert_eq!(y, expected);
+  }
+
+  #[test]
+  fn numeric_generic_test() {
+    use popcorn::num::Numeric;
+
+    fn sum_and_scale<T: Numeric + Sync + Send + 'static>(vs: Vec<T>, alpha: T) -> (T, Vec<T>) {
+      let backend = popcorn::frameworks::native::Backend::default();
+      let x = Buffer::from_vec_native(backend.device(), vs).unwrap().try_lock().unwrap();
+      let (x, sum) = backend.sum(x).wait().unwrap();
+      (sum, backend.mul_scalar(x, alpha).and_then(|x| x.sync_to_vec()).wait().unwrap())
+    }
+
+    assert_eq!(sum_and_scale(vec![1.5f64, 2.5], 2.0), (4.0, vec![3.0, 5.0]));
+    assert_eq!(sum_and_scale(vec![3i64, -1, 4], 3), (6, vec![9, -3, 12]));
+  }
+
+  #[test]
+  #[cfg(feature = "complex")]
+  fn complex_dot_test() {
+    use std::mem;
+
+    let backend = popcorn::frameworks::native::Backend::default();
+    let dev = backend.device();
+    assert_eq!(mem::size_of::<Complex<f32>>(), 2 * mem::size_of::<f32>());
+
+    let a = Tensor::new_native(dev, vec![2, 2], vec![Complex::new(1.0f32, 2.0), Complex::new(3.0, -1.0),
+                                                     Complex::new(0.0, 1.0), Complex::new(2.0, 0.0)]).unwrap();
+    let b = Tensor::new_native(dev, vec![2, 2], vec![Complex::new(2.0f32, 0.0), Complex::new(1.0, 1.0),
+                                                     Complex::new(0.0, 1.0), Complex::new(-1.0, 0.0)]).unwrap();
+    let (a, b) = a.lock().join(b.lock()).wait().unwrap();
+    let (shape_a, a) = a.into_buffers().unwrap();
+    let (shape_b, b) = b.into_buffers().unwrap();
+
+    let (shape_c, c) = backend.bcast_dot(shape_a, a, shape_b, b).wait().unwrap();
+    assert_eq!(shape_c.sync_to_vec().wait().unwrap(), vec![2]);
+    // (1+2i)(2) + (3-i)(1+i) = 6+6i, and (i)(i) + (2)(-1) = -3
+    assert_eq!(c.sync_to_vec().wait().unwrap(), vec![Complex::new(6.0, 6.0), Complex::new(-3.0, 0.0)]);
+
+    let x = Buffer::from_vec_native(dev, vec![Complex::new(1.0f64, -1.0); 3]).unwrap().try_lock().unwrap();
+    let n_x: &[Complex<f64>] = x.native_memory(dev).unwrap().try_as_slice().unwrap();
+    assert_eq!(n_x.len(), 3);
+    assert_eq!(x.native_memory(dev).unwrap().len(), 48);
+    assert_eq!(Complex::<f64>::dot(n_x, n_x), Complex::new(0.0, -6.0));
+  }
+
+  #[test]
+  fn sum_cancellable_test() {
+    use std::thread;
+    use std::time::Duration;
+
+    let backend = popcorn::frameworks::native::Backend::default();
+    let dev = backend.device();
+
+    let x = Buffer::from_vec_native(dev, vec![1.0f32; 16]).unwrap().try_lock().unwrap();
+    let (_, sum) = backend.sum_cancellable(x, CancelToken::new()).wait().unwrap();
+    assert_eq!(sum, 16.0);
+
+    // Cancelled while the kernel works through its chunks
+    let x = Buffer::from_vec_native(dev, vec![1.0f32; 1 << 24]).unwrap().try_lock().unwrap();
+    let token = CancelToken::new();
+    let canceller = {
+      let token = token.clone();
+      thread::spawn(move || {
+        thread::sleep(Duration::from_millis(1));
+        token.cancel();
+      })
+    };
+    match backend.sum_cancellable(x, token).wait() {
+      Err(popcorn::buffer::Error::Cancelled) => (),
+      r => panic!("expected the sum to be cancelled, got {:?}", r.map(|(_, s)| s))
+    }
+    canceller.join().unwrap();
+
+    // Cancelled before it ever ran
+    let token = CancelToken::new();
+    token.cancel();
+    let x = Buffer::from_vec_native(dev, vec![1.0f32; 16]).unwrap().try_lock().unwrap();
+    assert!(backend.sum_cancellable(x, token).wait().is_err());
+  }
+
+  #[test]
+  fn map_op_test() {
+    use futures::stream::{self, Stream};
+
+    let backend = popcorn::frameworks::native::Backend::default();
+    let dev = backend.device().clone();
+
+    let inputs = vec![vec![1.0f32, 2.0], vec![3.0, 4.0], vec![5.0, 6.0]];
+    let buffers = stream::iter_ok(inputs).and_then(move |v| {
+      try!(Buffer::from_vec_native(&dev, v)).try_lock()
+    });
+
+    let results = map_op(buffers, 2, |x| backend.mul_scalar(x, 10.0)).
+      and_then(|x| x.sync_to_vec()).
+      collect().wait().unwrap();
+    assert_eq!(results, vec![vec![10.0, 20.0], vec![30.0, 40.0], vec![50.0, 60.0]]);
+  }
+
+  #[test]
+  fn dot_trailing_mismatch_test() {
+    let backend = popcorn::frameworks::native::Backend::default();
+    let dev = backend.device();
+
+    let shape_a = Buffer::from_vec_native(dev, vec![2, 3]).unwrap().try_lock().unwrap();
+    let a = Buffer::from_vec_native(dev, vec![1.0f32; 6]).unwrap().try_lock().unwrap();
+    let shape_b = Buffer::from_vec_native(dev, vec![2, 1]).unwrap().try_lock().unwrap();
+    let b = Buffer::from_vec_native(dev, vec![1.0f32; 2]).unwrap().try_lock().unwrap();
+
+    match backend.bcast_dot(shape_a, a, shape_b, b).wait() {
+      Err(popcorn::buffer::Error::BroadcastError { axis, .. }) => assert_eq!(axis, 1),
+      _ => panic!("expected a broadcast error")
+    }
+  }
 }
@@ -1,5 +1,6 @@
 use futures::Future;
 use popcorn::buffer::{LockedBuffer, Error};
+use popcorn::cancel::CancelToken;
 
 pub trait DotOperation<T: Copy + Send + 'static> {
   fn bcast_dot(&self,
@@ -9,3 +10,119 @@ pub trait DotOperation<T: Copy + Send + 'static> {
                b: LockedBuffer<T>) ->
     Box<Future<Item=(LockedBuffer<usize>, LockedBuffer<T>), Error=Error>>; // Result
 }
+
+pub trait GemmOperation<T: Copy + Send + 'static> {
+  fn bcast_gemm(&self,
+                shape_a: LockedBuffer<usize>,
+                a: LockedBuffer<T>,
+                shape_b: LockedBuffer<usize>,
+                b: LockedBuffer<T>) ->
+    Box<Future<Item=(LockedBuffer<usize>, LockedBuffer<T>), Error=Error>>; // Result
+}
+
+pub trait GemvOperation<T: Copy + Send + 'static> {
+  fn gemv(&self,
+          shape_a: LockedBuffer<usize>,
+          a: LockedBuffer<T>,
+          x: LockedBuffer<T>) ->
+    Box<Future<Item=(LockedBuffer<usize>, LockedBuffer<T>), Error=Error>>; // Result
+}
+
+pub trait AxpyOperation<T: Copy + Send + 'static> {
+  fn axpy(&self,
+          alpha: T,
+          x: LockedBuffer<T>,
+          y: LockedBuffer<T>) -> Box<Future<Item=LockedBuffer<T>, Error=Error>>;
+}
+
+pub trait ScalOperation<T: Copy + Send + 'static> {
+  fn scal(&self,
+          alpha: T,
+          x: LockedBuffer<T>) -> Box<Future<Item=LockedBuffer<T>, Error=Error>>;
+}
+
+pub trait NormOperation<T: Copy + Send + 'static> {
+  fn nrm2(&self,
+          x: LockedBuffer<T>) -> Box<Future<Item=(LockedBuffer<T>, T), Error=Error>>;
+
+  fn asum(&self,
+          x: LockedBuffer<T>) -> Box<Future<Item=(LockedBuffer<T>, T), Error=Error>>;
+}
+
+pub trait ReduceOperation<T: Copy + Send + 'static> {
+  fn sum(&self,
+         x: LockedBuffer<T>) -> Box<Future<Item=(LockedBuffer<T>, T), Error=Error>>;
+
+  fn min(&self,
+         x: LockedBuffer<T>) -> Box<Future<Item=(LockedBuffer<T>, T), Error=Error>>;
+
+  fn max(&self,
+         x: LockedBuffer<T>) -> Box<Future<Item=(LockedBuffer<T>, T), Error=Error>>;
+
+  fn mean(&self,
+          x: LockedBuffer<T>) -> Box<Future<Item=(LockedBuffer<T>, T), Error=Error>>;
+
+  /// Like `sum`, but gives up with `Error::Cancelled` once `token` is
+  /// cancelled, even part way through.
+  fn sum_cancellable(&self,
+                     x: LockedBuffer<T>,
+                     token: CancelToken) -> Box<Future<Item=(LockedBuffer<T>, T), Error=Error>>;
+}
+
+pub trait ArgReduceOperation<T: Copy + Send + 'static> {
+  fn argmax(&self,
+            x: LockedBuffer<T>) -> Box<Future<Item=(LockedBuffer<T>, usize), Error=Error>>;
+
+  fn argmin(&self,
+            x: LockedBuffer<T>) -> Box<Future<Item=(LockedBuffer<T>, usize), Error=Error>>;
+}
+
+pub trait ActivationOperation<T: Copy + Send + 'static> {
+  fn relu(&self,
+          x: LockedBuffer<T>) -> Box<Future<Item=LockedBuffer<T>, Error=Error>>;
+
+  fn sigmoid(&self,
+             x: LockedBuffer<T>) -> Box<Future<Item=LockedBuffer<T>, Error=Error>>;
+
+  fn tanh(&self,
+          x: LockedBuffer<T>) -> Box<Future<Item=LockedBuffer<T>, Error=Error>>;
+}
+
+pub trait SoftmaxOperation<T: Copy + Send + 'static> {
+  fn softmax(&self,
+             x: LockedBuffer<T>) -> Box<Future<Item=LockedBuffer<T>, Error=Error>>;
+
+  // Softmax over the last axis of `shape`
+  fn softmax_last_axis(&self,
+                       shape: LockedBuffer<usize>,
+                       x: LockedBuffer<T>) ->
+    Box<Future<Item=(LockedBuffer<usize>, LockedBuffer<T>), Error=Error>>;
+}
+
+// Each element of x combined with alpha in place. Integer division by
+// zero is an error, float division follows IEEE 754 and yields an
+// infinity or NaN.
+pub trait ScalarOperation<T: Copy + Send + 'static> {
+  fn add_scalar(&self,
+                x: LockedBuffer<T>,
+                alpha: T) -> Box<Future<Item=LockedBuffer<T>, Error=Error>>;
+
+  fn sub_scalar(&self,
+                x: LockedBuffer<T>,
+                alpha: T) -> Box<Future<Item=LockedBuffer<T>, Error=Error>>;
+
+  fn mul_scalar(&self,
+                x: LockedBuffer<T>,
+                alpha: T) -> Box<Future<Item=LockedBuffer<T>, Error=Error>>;
+
+  fn div_scalar(&self,
+                x: LockedBuffer<T>,
+                alpha: T) -> Box<Future<Item=LockedBuffer<T>, Error=Error>>;
+}
+
+pub trait ClampOperation<T: Copy + Send + 'static> {
+  fn clamp(&self,
+           x: LockedBuffer<T>,
+           min: T,
+           max: T) -> Box<Future<Item=LockedBuffer<T>, Error=Error>>;
+}
@@ -0,0 +1,311 @@
+//! Tensors bundle a buffer with its shape and the device it lives on.
+//!
+//! The arithmetic operators and `matmul` are deferred: they return a
+//! future of the resulting tensor rather than computing it, and nothing
+//! runs until that future is polled.
+
+use std::ops;
+use futures::{Future, IntoFuture};
+use popcorn::frameworks::native;
+use popcorn::buffer::{Buffer, LockedBuffer, Error};
+use popcorn::num::{Zero, One, Numeric};
+use operation::GemmOperation;
+use frameworks::native::broadcast;
+use frameworks::native::core_ops::Gemm;
+
+/// A buffer along with the shape of its contents, in row major order.
+#[derive(Clone)]
+pub struct Tensor<T> {
+  data: Buffer<T>,
+  shape: Vec<usize>,
+  dev: native::Device
+}
+
+pub struct LockedTensor<T> {
+  data: LockedBuffer<T>,
+  shape: Vec<usize>,
+  dev: native::Device
+}
+
+impl<T: Send + Copy + Sized + 'static> Tensor<T> {
+  pub fn new_native(dev: &native::Device, shape: Vec<usize>, data: Vec<T>) -> Result<Tensor<T>, Error> {
+    let len = shape.iter().product();
+    if data.len() != len {
+      return Err(Error::SizeMismatch { expected: len, actual: data.len() })
+    }
+
+    Ok(Tensor {
+      data: try!(Buffer::from_vec_native(dev, data)),
+      shape: shape,
+      dev: dev.clone()
+    })
+  }
+
+  /// An `n` x `n` identity matrix, built on the device's workers.
+  pub fn eye_native(dev: &native::Device, n: usize) -> Box<Future<Item=Tensor<T>,Error=Error>>
+    where T: Zero + One {
+    let dev = dev.clone();
+    Box::new(dev.pool().clone().spawn_fn(move || {
+      let mut data = try!(try!(Buffer::new(&dev, n * n)).try_lock());
+      {
+        let s: &mut [T] = try!(try!(data.native_memory_mut(&dev)).try_as_mut_slice());
+        for x in s.iter_mut() {
+          *x = T::zero();
+        }
+        for i in 0..n {
+          s[i * n + i] = T::one();
+        }
+      }
+
+      Ok(Tensor {
+        data: data.into(),
+        shape: vec![n, n],
+        dev: dev
+      })
+    }))
+  }
+
+  /// The same data viewed with `new_shape`, which must hold the same
+  /// number of elements. Nothing is copied.
+  pub fn reshape(self, new_shape: Vec<usize>) -> Result<Tensor<T>, Error> {
+    let len = new_shape.iter().product();
+    if len != self.len() {
+      return Err(Error::SizeMismatch { expected: self.len(), actual: len })
+    }
+
+    Ok(Tensor {
+      data: self.data,
+      shape: new_shape,
+      dev: self.dev
+    })
+  }
+
+  /// Like `reshape`, but a single dimension may be `-1` to have it
+  /// inferred from the others.
+  pub fn reshape_infer(self, new_shape: &[isize]) -> Result<Tensor<T>, Error> {
+    let inferred: Vec<usize> = new_shape.iter().enumerate().filter(|&(_, d)| *d == -1).map(|(i, _)| i).collect();
+    if inferred.len() > 1 || new_shape.iter().any(|d| *d < -1) {
+      return Err(Error::InvalidShape)
+    }
+
+    let mut shape: Vec<usize> = new_shape.iter().map(|d| if *d == -1 { 1 } else { *d as usize }).collect();
+    if let Some(&i) = inferred.first() {
+      let known: usize = shape.iter().product();
+      if known == 0 || self.len() % known != 0 {
+        return Err(Error::InvalidShape)
+      }
+      shape[i] = self.len() / known;
+    }
+
+    self.reshape(shape)
+  }
+
+  /// Deferred transpose of a 2 dimensional tensor, copying the data
+  /// into the new layout on the device's workers.
+  pub fn transpose(&self) -> Box<Future<Item=Tensor<T>,Error=Error>> {
+    if self.shape.len() != 2 {
+      return Box::new(Err(Error::InvalidShape).into_future())
+    }
+
+    let (m, n) = (self.shape[0], self.shape[1]);
+    let dev = self.dev.clone();
+    let pool = self.dev.pool().clone();
+    Box::new(self.data.lock().and_then(move |a| a.sync(&dev).map(move |a| (a, dev))).and_then(move |(a, dev)| {
+      pool.spawn_fn(move || {
+        let mut b = try!(try!(Buffer::with_capacity_native(&dev, m * n)).try_lock());
+
+        {
+          let n_a: &[T] = try!(try!(a.native_memory(&dev)).try_as_slice());
+          let n_b: &mut [T] = try!(try!(b.native_memory_mut(&dev)).try_as_mut_slice());
+          for i in 0..m {
+            for j in 0..n {
+              n_b[j * m + i] = n_a[i * n + j];
+            }
+          }
+        }
+
+        Ok(Tensor {
+          data: b.into(),
+          shape: vec![n, m],
+          dev: dev
+        })
+      })
+    }))
+  }
+
+  pub fn shape(&self) -> &[usize] { &self.shape }
+
+  /// Number of elements, the product of the shape.
+  pub fn len(&self) -> usize { self.shape.iter().product() }
+
+  pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+  pub fn data(&self) -> &Buffer<T> { &self.data }
+
+  pub fn device(&self) -> &native::Device { &self.dev }
+
+  pub fn lock(&self) -> Box<Future<Item=LockedTensor<T>,Error=Error>> {
+    let shape = self.shape.clone();
+    let dev = self.dev.clone();
+    Box::new(self.data.lock().map(move |data| LockedTensor {
+      data: data,
+      shape: shape,
+      dev: dev
+    }))
+  }
+
+  // Lock both tensors' data on this tensor's device. A tensor used as
+  // both operands is copied, as its buffer can only be locked once.
+  fn lock_with(&self, other: &Tensor<T>) -> Box<Future<Item=(LockedBuffer<T>, LockedBuffer<T>),Error=Error>> {
+    let dev = self.dev.clone();
+    let locked: Box<Future<Item=(LockedBuffer<T>, LockedBuffer<T>),Error=Error>> =
+      if Buffer::ptr_eq(&self.data, &other.data) {
+        let copy_dev = dev.clone();
+        Box::new(self.data.lock().and_then(move |a| {
+          a.deep_copy(&copy_dev).and_then(|(a, b)| b.lock().map(move |b| (a, b)))
+        }))
+      } else {
+        Box::new(self.data.lock().join(other.data.lock()))
+      };
+
+    Box::new(locked.and_then(move |(a, b)| a.sync(&dev).join(b.sync(&dev))))
+  }
+
+  // Broadcast against `other` and combine element by element with `op`
+  // on the device pool
+  fn elementwise(&self, other: &Tensor<T>, op: fn(T, T) -> T) -> Box<Future<Item=Tensor<T>,Error=Error>>
+    where T: Sync {
+    if let Some(axis) = broadcast::incompatible_axis(&self.shape, &other.shape) {
+      return Box::new(Err(Error::BroadcastError {
+        shape_a: self.shape.clone(),
+        shape_b: other.shape.clone(),
+        axis: axis
+      }).into_future())
+    }
+
+    let shape_a = self.shape.clone();
+    let shape_b = other.shape.clone();
+    let dev = self.dev.clone();
+    let pool = self.dev.pool().clone();
+    Box::new(self.lock_with(other).and_then(move |(a, b)| {
+      pool.spawn_fn(move || {
+        let n_a: &[T] = try!(try!(a.native_memory(&dev)).try_as_slice());
+        let n_b: &[T] = try!(try!(b.native_memory(&dev)).try_as_slice());
+
+        let (bshape, iter_a, iter_b) = try!(broadcast::try_new_broadcast(&shape_a, n_a, &shape_b, n_b, 0));
+        let mut c = try!(try!(Buffer::with_capacity_native(&dev, bshape.iter().product())).try_lock());
+
+        {
+          let n_c: &mut [T] = try!(try!(c.native_memory_mut(&dev)).try_as_mut_slice());
+          for ((x, y), v) in iter_a.zip(iter_b).zip(n_c.iter_mut()) {
+            *v = op(x[0], y[0]);
+          }
+        }
+
+        Ok(Tensor {
+          data: c.into(),
+          shape: bshape,
+          dev: dev
+        })
+      })
+    }))
+  }
+}
+
+impl<T: Gemm + Sync + Send + Copy + Sized + 'static> Tensor<T> {
+  /// Deferred matrix product of two 2 dimensional tensors.
+  pub fn matmul(&self, other: &Tensor<T>) -> Box<Future<Item=Tensor<T>,Error=Error>> {
+    let backend = native::Backend::from(self.dev.clone());
+    let shape_a = self.shape.clone();
+    let shape_b = other.shape.clone();
+    let dev = self.dev.clone();
+    let out_dev = self.dev.clone();
+
+    Box::new(self.lock_with(other).and_then(move |(a, b)| {
+      let shape_a = try!(try!(Buffer::from_vec_native(&dev, shape_a)).try_lock());
+      let shape_b = try!(try!(Buffer::from_vec_native(&dev, shape_b)).try_lock());
+      Ok((shape_a, a, shape_b, b))
+    }).and_then(move |(shape_a, a, shape_b, b)| {
+      backend.bcast_gemm(shape_a, a, shape_b, b)
+    }).and_then(move |(shape_c, c)| {
+      shape_c.sync_to_vec().map(move |shape| Tensor {
+        data: c.into(),
+        shape: shape,
+        dev: out_dev
+      })
+    }))
+  }
+}
+
+impl<'a, T: Numeric + Sync + Send + 'static> ops::Add for &'a Tensor<T> {
+  type Output = Box<Future<Item=Tensor<T>,Error=Error>>;
+
+  /// Deferred, broadcasting elementwise sum.
+  fn add(self, other: &'a Tensor<T>) -> Self::Output {
+    self.elementwise(other, |x, y| x + y)
+  }
+}
+
+impl<'a, T: Numeric + Sync + Send + 'static> ops::Sub for &'a Tensor<T> {
+  type Output = Box<Future<Item=Tensor<T>,Error=Error>>;
+
+  /// Deferred, broadcasting elementwise difference.
+  fn sub(self, other: &'a Tensor<T>) -> Self::Output {
+    self.elementwise(other, |x, y| x - y)
+  }
+}
+
+impl<'a, T: Numeric + Sync + Send + 'static> ops::Mul for &'a Tensor<T> {
+  type Output = Box<Future<Item=Tensor<T>,Error=Error>>;
+
+  /// Deferred, broadcasting elementwise product.
+  fn mul(self, other: &'a Tensor<T>) -> Self::Output {
+    self.elementwise(other, |x, y| x * y)
+  }
+}
+
+impl<T: Send + Copy + Sized + 'static> LockedTensor<T> {
+  /// Wrap a locked buffer on `dev` holding the contents of a `shape`
+  /// array.
+  pub fn from_buffer(dev: &native::Device,
+                     shape: Vec<usize>,
+                     data: LockedBuffer<T>) -> Result<LockedTensor<T>, Error> {
+    let len = shape.iter().product();
+    if data.size() != len {
+      return Err(Error::SizeMismatch { expected: len, actual: data.size() })
+    }
+
+    Ok(LockedTensor {
+      data: data,
+      shape: shape,
+      dev: dev.clone()
+    })
+  }
+
+  pub fn shape(&self) -> &[usize] { &self.shape }
+
+  pub fn len(&self) -> usize { self.data.size() }
+
+  pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+  pub fn data(&self) -> &LockedBuffer<T> { &self.data }
+
+  pub fn data_mut(&mut self) -> &mut LockedBuffer<T> { &mut self.data }
+
+  /// Split into a shape buffer and the data, as taken by the
+  /// broadcasting operations.
+  pub fn into_buffers(self) -> Result<(LockedBuffer<usize>, LockedBuffer<T>), Error> {
+    let shape = try!(try!(Buffer::from_vec_native(&self.dev, self.shape)).try_lock());
+    Ok((shape, self.data))
+  }
+}
+
+impl<T> From<LockedTensor<T>> for Tensor<T> {
+  fn from(locked: LockedTensor<T>) -> Tensor<T> {
+    Tensor {
+      data: locked.data.into(),
+      shape: locked.shape,
+      dev: locked.dev
+    }
+  }
+}
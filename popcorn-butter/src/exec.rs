@@ -1,5 +1,6 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::marker::PhantomData;
+use std::cmp;
 use std::sync::Arc;
 use std::ops::Deref;
 use futures::{Future, IntoFuture};
@@ -17,11 +18,27 @@ pub enum Error {
   PlaceholderError,
   NoSuchElement,
   Buffer(buffer::Error),
-  DowncastError
+  DowncastError,
+  NotDifferentiable
 }
 
+/// Caches the outputs of every executable in a graph.
+///
+/// `exec` only wires up futures, no work happens until an output is
+/// polled. Each node's outputs are `Shared`, so a node runs once no
+/// matter how many sockets read it, and ops join their inputs so
+/// independent branches of a graph run concurrently on the device
+/// pools.
+///
+/// Computed outputs can be evicted to release their buffers, an evicted
+/// node is recomputed the next time it is needed. Inputs cannot be
+/// recomputed, they have to be set again after being evicted.
 pub struct Context {
   cache: HashMap<Uuid, Vec<Box<Any>>>,
+
+  // Computed nodes, least recently used first
+  order: VecDeque<Uuid>,
+  capacity: Option<usize>
 }
 
 pub trait Executable {
@@ -46,27 +63,68 @@ impl<Base: 'static> Socket<Base> {
     }
   }
 
+  pub fn uid(&self) -> &Uuid { self.executable.uid() }
+  pub fn index(&self) -> usize { self.index }
+
   pub fn exec(&self, ctx: &mut Context) -> Result<SBBF<Base>, Error> {
     ctx.try_caching(self.executable.deref(), self.index)
   }
 }
 
+impl<Base> Clone for Socket<Base> {
+  fn clone(&self) -> Socket<Base> {
+    Socket {
+      executable: self.executable.clone(),
+      index: self.index,
+
+      _pd: PhantomData { }
+    }
+  }
+}
+
 impl Context {
   pub fn new() -> Context {
     Context {
-      cache: HashMap::new()
+      cache: HashMap::new(),
+      order: VecDeque::new(),
+      capacity: None
+    }
+  }
+
+  /// Keep at most `capacity` computed nodes cached, evicting the least
+  /// recently used ones first. Inputs do not count towards the capacity.
+  pub fn with_capacity(capacity: usize) -> Context {
+    Context {
+      capacity: Some(capacity),
+      ..Context::new()
     }
   }
 
   pub fn cache_executable(&mut self, e: &Executable) -> Result<(), Error> {
-    if !self.cache.contains_key(e.uid()) {
+    if self.cache.contains_key(e.uid()) {
+      self.touch(e.uid());
+    } else {
       let items = try!(e.exec(self));
       self.cache.insert(e.uid().clone(), items);
+      self.order.push_back(e.uid().clone());
+      self.shrink();
     }
 
     Ok(())
   }
 
+  /// Drop the cached outputs of a node, returns whether it was cached.
+  pub fn evict(&mut self, uid: &Uuid) -> bool {
+    self.forget(uid);
+    self.cache.remove(uid).is_some()
+  }
+
+  /// Drop every cached output, including inputs.
+  pub fn clear(&mut self) {
+    self.cache.clear();
+    self.order.clear();
+  }
+
   pub fn try_caching<Base: 'static>(&mut self,
                                     e: &Executable,
                                     index: usize) -> Result<SBBF<Base>, Error> {
@@ -83,7 +141,101 @@ impl Context {
                                                                   buf: B) -> &mut Self {
     let f: Box<Future<Item=Buffer<Base>,Error=buffer::Error>> = Box::new(buf.into_future());
     let bf = Box::new(f.shared()) as Box<Any>;
+    self.forget(&uid);
     self.cache.insert(uid, vec![bf]);
     self
   }
+
+  fn touch(&mut self, uid: &Uuid) {
+    if let Some(i) = self.order.iter().position(|u| u == uid) {
+      let uid = self.order.remove(i).unwrap();
+      self.order.push_back(uid);
+    }
+  }
+
+  fn forget(&mut self, uid: &Uuid) {
+    if let Some(i) = self.order.iter().position(|u| u == uid) {
+      self.order.remove(i);
+    }
+  }
+
+  // The node that was just cached is always kept, it is about to be
+  // read by the caller
+  fn shrink(&mut self) {
+    if let Some(capacity) = self.capacity {
+      while self.order.len() > cmp::max(capacity, 1) {
+        let uid = self.order.pop_front().unwrap();
+        self.cache.remove(&uid);
+      }
+    }
+  }
+}
+
+#[cfg(all(test, feature = "native"))]
+mod test {
+  use super::*;
+  use popcorn::{native, Backend};
+  use ops::Placeholder;
+  use ops::native::Add;
+
+  fn add_of(a: &Arc<Placeholder<f32>>, b: &Arc<Placeholder<f32>>, backend: &native::Backend) -> Arc<Add<f32>> {
+    Arc::new(Add::new(vec![3], Socket::<f32>::new(a.clone(), 0),
+                      vec![3], Socket::<f32>::new(b.clone(), 0),
+                      backend.clone()))
+  }
+
+  fn read(socket: &Socket<f32>, ctx: &mut Context) -> Vec<f32> {
+    socket.exec(ctx).unwrap().
+      map_err(|e| (*e).clone()).
+      and_then(|b| b.lock().and_then(|b| b.sync_to_vec())).wait().unwrap()
+  }
+
+  #[test]
+  fn test_evict_releases_memory() {
+    let backend = native::Backend::default();
+    let dev = backend.device();
+    let pa = Arc::new(Placeholder::<f32>::new());
+    let pb = Arc::new(Placeholder::<f32>::new());
+    let add = add_of(&pa, &pb, &backend);
+
+    let mut ctx = Context::new();
+    ctx.set_input(pa.uid().clone(), Buffer::from_vec_native(dev, vec![1.0f32, 2.0, 3.0])).
+      set_input(pb.uid().clone(), Buffer::from_vec_native(dev, vec![1.0f32, 1.0, 1.0]));
+    let before = dev.allocated_bytes();
+
+    let c = Socket::<f32>::new(add.clone(), 0);
+    assert_eq!(read(&c, &mut ctx), vec![2.0, 3.0, 4.0]);
+    assert!(dev.allocated_bytes() > before);
+
+    assert!(ctx.evict(add.uid()));
+    assert!(!ctx.evict(add.uid()));
+    assert_eq!(dev.allocated_bytes(), before);
+
+    // Evicted nodes are recomputed on demand
+    assert_eq!(read(&c, &mut ctx), vec![2.0, 3.0, 4.0]);
+
+    ctx.clear();
+    assert_eq!(dev.allocated_bytes(), 0);
+  }
+
+  #[test]
+  fn test_lru_capacity() {
+    let backend = native::Backend::default();
+    let dev = backend.device();
+    let pa = Arc::new(Placeholder::<f32>::new());
+    let pb = Arc::new(Placeholder::<f32>::new());
+    let add_1 = add_of(&pa, &pb, &backend);
+    let add_2 = add_of(&pa, &pb, &backend);
+
+    let mut ctx = Context::with_capacity(1);
+    ctx.set_input(pa.uid().clone(), Buffer::from_vec_native(dev, vec![1.0f32, 2.0, 3.0])).
+      set_input(pb.uid().clone(), Buffer::from_vec_native(dev, vec![1.0f32, 1.0, 1.0]));
+
+    read(&Socket::new(add_1.clone(), 0), &mut ctx);
+    read(&Socket::new(add_2.clone(), 0), &mut ctx);
+
+    assert!(!ctx.evict(add_1.uid()));
+    assert!(ctx.evict(add_2.uid()));
+    assert!(ctx.evict(pa.uid()));
+  }
 }
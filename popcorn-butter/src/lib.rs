@@ -1,7 +1,10 @@
 extern crate uuid;
 extern crate futures;
+#[cfg(test)]
+extern crate futures_cpupool;
 extern crate popcorn;
 extern crate popcorn_blas;
 
 pub mod exec;
 pub mod ops;
+pub mod tape;
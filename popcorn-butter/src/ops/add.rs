@@ -0,0 +1,152 @@
+use uuid::Uuid;
+use exec::*;
+use ops::elementwise;
+
+#[cfg(feature = "native")]
+pub mod native {
+  use super::*;
+  use popcorn::native;
+  use popcorn::num::Numeric;
+
+  pub struct Add<T> {
+    uid: Uuid,
+    shape_a: Vec<usize>,
+    a: Socket<T>,
+    shape_b: Vec<usize>,
+    b: Socket<T>,
+    backend: native::Backend
+  }
+
+  impl<T> Add<T> {
+    pub fn new(shape_a: Vec<usize>,
+               a: Socket<T>,
+               shape_b: Vec<usize>,
+               b: Socket<T>,
+               backend: native::Backend) -> Add<T> {
+      Add {
+        uid: Uuid::new_v4(),
+        shape_a: shape_a,
+        a: a,
+        shape_b: shape_b,
+        b: b,
+        backend: backend
+      }
+    }
+  }
+
+  /// Outputs are the elementwise sum at index 0 and its shape at index 1.
+  impl<T: Numeric + Sync + Send + 'static> Executable for Add<T> {
+    fn uid(&self) -> &Uuid { &self.uid }
+
+    fn exec<'a>(&self, ctx: &'a mut Context) ->
+      Result<Vec<Box<Any>>, Error> {
+        elementwise::native::exec(&self.shape_a, &self.a, &self.shape_b, &self.b,
+                                  &self.backend, ctx, add::<T>)
+      }
+  }
+
+  fn add<T: Numeric>(x: T, y: T) -> T { x + y }
+
+  #[cfg(test)]
+  mod test {
+    use super::*;
+    use ops::Placeholder;
+    use popcorn::Backend;
+    use futures::Future;
+    use ops::native::Dot;
+    use futures::future;
+    use std::sync::Arc;
+    use std::sync::mpsc::{channel, Sender, Receiver};
+    use std::time::Duration;
+    use futures_cpupool::CpuPool;
+
+    #[test]
+    fn test_add_exec() {
+      let backend = native::Backend::default();
+      let a = Buffer::from_vec_native(backend.device(), vec![1.0f32, 2.0, 3.0]).unwrap();
+      let b = Buffer::from_vec_native(backend.device(), vec![10.0f32, 10.0, 10.0]).unwrap();
+
+      let pa = Arc::new(Placeholder::<f32>::new());
+      let pb = Arc::new(Placeholder::<f32>::new());
+      let add = Arc::new(Add::new(vec![3], Socket::<f32>::new(pa.clone(), 0),
+                                  vec![3], Socket::<f32>::new(pb.clone(), 0),
+                                  backend.clone()));
+
+      let mut ctx = Context::new();
+      ctx.set_input(pa.uid().clone(), Ok(a)).set_input(pb.uid().clone(), Ok(b));
+
+      let c = Socket::<f32>::new(add, 0);
+      let rv = c.exec(&mut ctx).unwrap().
+        map_err(|e| (*e).clone()).
+        and_then(|b| b.lock().and_then(|b| b.sync_to_vec())).wait().unwrap();
+      assert_eq!(rv, vec![11.0, 12.0, 13.0]);
+    }
+
+    // Only produces its buffer once the other branch has asked for its
+    // input too, so it never completes if the branches run one by one
+    fn rendezvous(pool: &CpuPool, dev: &native::Device, value: Vec<f32>,
+                  arrived: Sender<()>, other: Receiver<()>) ->
+      Box<Future<Item=Buffer<f32>,Error=buffer::Error>> {
+        let pool = pool.clone();
+        let dev = dev.clone();
+
+        Box::new(future::lazy(move || pool.spawn_fn(move || {
+          arrived.send(()).unwrap();
+          other.recv_timeout(Duration::from_secs(10)).expect("the other branch never ran");
+          Buffer::from_vec_native(&dev, value)
+        })))
+      }
+
+    #[test]
+    fn test_add_runs_branches_concurrently() {
+      let backend = native::Backend::default();
+      let pool = CpuPool::new(2);
+      let (tx1, rx1) = channel();
+      let (tx2, rx2) = channel();
+
+      let dot = |a: Vec<f32>, b: Vec<f32>, arrived: Sender<()>, other: Receiver<()>, ctx: &mut Context| {
+        let pa = Arc::new(Placeholder::<f32>::new());
+        let pb = Arc::new(Placeholder::<f32>::new());
+        let b = Buffer::from_vec_native(backend.device(), b).unwrap();
+        ctx.set_input(pa.uid().clone(), rendezvous(&pool, backend.device(), a, arrived, other)).
+          set_input(pb.uid().clone(), Ok(b));
+
+        Arc::new(Dot::new(vec![2], Socket::<f32>::new(pa, 0),
+                          vec![2], Socket::<f32>::new(pb, 0),
+                          backend.clone()).unwrap())
+      };
+
+      let mut ctx = Context::new();
+      let d1 = dot(vec![1.0, 2.0], vec![3.0, 4.0], tx1, rx2, &mut ctx);
+      let d2 = dot(vec![5.0, 6.0], vec![7.0, 8.0], tx2, rx1, &mut ctx);
+      let add = Arc::new(Add::new(vec![], Socket::<f32>::new(d1, 0),
+                                  vec![], Socket::<f32>::new(d2, 0),
+                                  backend.clone()));
+
+      // The second pass reads the cache, a rerun would find no partner
+      let c = Socket::<f32>::new(add, 0);
+      for _ in 0..2 {
+        let rv = c.exec(&mut ctx).unwrap().
+          map_err(|e| (*e).clone()).
+          and_then(|b| b.lock().and_then(|b| b.sync_to_vec())).wait().unwrap();
+        assert_eq!(rv, vec![94.0]);
+      }
+    }
+
+    #[test]
+    fn test_add_incompatible_shapes() {
+      let backend = native::Backend::default();
+      let pa = Arc::new(Placeholder::<f32>::new());
+      let pb = Arc::new(Placeholder::<f32>::new());
+      let add = Arc::new(Add::new(vec![3], Socket::<f32>::new(pa.clone(), 0),
+                                  vec![2], Socket::<f32>::new(pb.clone(), 0),
+                                  backend));
+
+      let mut ctx = Context::new();
+      match Socket::<f32>::new(add, 0).exec(&mut ctx) {
+        Err(Error::Buffer(buffer::Error::BroadcastError { axis, .. })) => assert_eq!(axis, 0),
+        _ => panic!("expected a broadcast error")
+      }
+    }
+  }
+}
@@ -0,0 +1,26 @@
+use uuid::Uuid;
+use exec::*;
+use futures::{future, Future};
+
+/// Yields the same buffer every time it is executed.
+pub struct Constant<T> {
+  uid: Uuid,
+  buffer: Buffer<T>
+}
+
+impl<T> Constant<T> {
+  pub fn new(buffer: Buffer<T>) -> Constant<T> {
+    Constant {
+      uid: Uuid::new_v4(),
+      buffer: buffer
+    }
+  }
+}
+
+impl<T: Clone + 'static> Executable for Constant<T> {
+  fn uid(&self) -> &Uuid { &self.uid }
+  fn exec<'a>(&self, _ctx: &'a mut Context) -> Result<Vec<Box<Any>>, Error> {
+    let f: Box<Future<Item=Buffer<T>,Error=buffer::Error>> = Box::new(future::ok(self.buffer.clone()));
+    Ok(vec![Box::new(f.shared()) as Box<Any>])
+  }
+}
@@ -9,7 +9,7 @@ pub mod native {
   use popcorn::native;
   use popcorn_blas::frameworks::native::core_ops;
   use popcorn_blas::*;
-  use std::cmp;
+  use std::fmt;
 
   pub struct Dot<T> {
     uid: Uuid,
@@ -40,7 +40,8 @@ pub mod native {
     }
   }
 
-  impl<T: 'static> Executable for Dot<T> {
+  /// Outputs are the product at index 0 and its shape at index 1.
+  impl<T: core_ops::Dot + fmt::Debug + Sync + Copy + Send + 'static> Executable for Dot<T> {
     fn uid(&self) -> &Uuid { &self.uid }
 
     fn exec<'a>(&self, ctx: &'a mut Context) ->
@@ -49,14 +50,59 @@ pub mod native {
         let sbr = self.shape_b.lock();
         let ar = try!(self.a.exec(ctx));
         let br = try!(self.b.exec(ctx));
-        let backend = self.backend().clone();
+        let backend = self.backend.clone();
 
-        ar.join(br).join(sar.join(sbr)).and_then(move |((a, b), (sa, sb))| {
-          let (sc, c) = backend.bcast_dot(sa, a, sb, b);
+        let inputs = ar.join(br).map_err(|e| (*e).clone()).and_then(|(a, b)| {
+          a.lock().join(b.lock())
         });
+        let rf: Box<Future<Item=(Buffer<usize>, Buffer<T>),Error=buffer::Error>> =
+          Box::new(inputs.join(sar.join(sbr)).and_then(move |((a, b), (sa, sb))| {
+            backend.bcast_dot(sa, a, sb, b)
+          }).map(|(sc, c)| (sc.into(), c.into())));
+        let rf = rf.shared();
 
-        Err(Error::PlaceholderError)
+        let c: Box<Future<Item=Buffer<T>,Error=buffer::Error>> =
+          Box::new(rf.clone().map(|r| r.1.clone()).map_err(|e| (*e).clone()));
+        let sc: Box<Future<Item=Buffer<usize>,Error=buffer::Error>> =
+          Box::new(rf.map(|r| r.0.clone()).map_err(|e| (*e).clone()));
+
+        Ok(vec![Box::new(c.shared()) as Box<Any>,
+                Box::new(sc.shared()) as Box<Any>])
       }
   }
-}
 
+  #[cfg(test)]
+  mod test {
+    use super::*;
+    use ops::Placeholder;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_dot_exec() {
+      let backend = native::Backend::default();
+      let a = Buffer::from_vec_native(backend.device(), vec![1.0f32, 2.0, 3.0]).unwrap();
+      let b = Buffer::from_vec_native(backend.device(), vec![4.0f32, 5.0, 6.0]).unwrap();
+
+      let pa = Arc::new(Placeholder::<f32>::new());
+      let pb = Arc::new(Placeholder::<f32>::new());
+      let dot = Arc::new(Dot::new(vec![3], Socket::<f32>::new(pa.clone(), 0),
+                                  vec![3], Socket::<f32>::new(pb.clone(), 0),
+                                  backend.clone()).unwrap());
+
+      let mut ctx = Context::new();
+      ctx.set_input(pa.uid().clone(), Ok(a)).set_input(pb.uid().clone(), Ok(b));
+
+      let c = Socket::<f32>::new(dot.clone(), 0);
+      let rv = c.exec(&mut ctx).unwrap().
+        map_err(|e| (*e).clone()).
+        and_then(|b| b.lock().and_then(|b| b.sync_to_vec())).wait().unwrap();
+      assert_eq!(rv, vec![32.0]);
+
+      let sc = Socket::<usize>::new(dot, 1);
+      let shape = sc.exec(&mut ctx).unwrap().
+        map_err(|e| (*e).clone()).
+        and_then(|b| b.lock().and_then(|b| b.sync_to_vec())).wait().unwrap();
+      assert_eq!(shape, Vec::<usize>::new());
+    }
+  }
+}
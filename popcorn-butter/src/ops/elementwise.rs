@@ -0,0 +1,73 @@
+use exec::*;
+use futures::Future;
+
+#[cfg(feature = "native")]
+pub mod native {
+  use super::*;
+  use popcorn::Backend;
+  use popcorn::native;
+  use popcorn_blas::frameworks::native::broadcast;
+
+  // Broadcast two sockets against each other and combine them element
+  // by element with `op` on the native device pool. Outputs are the
+  // result at index 0 and its shape at index 1.
+  pub fn exec<T: Sync + Copy + Send + 'static>(shape_a: &[usize],
+                                               a: &Socket<T>,
+                                               shape_b: &[usize],
+                                               b: &Socket<T>,
+                                               backend: &native::Backend,
+                                               ctx: &mut Context,
+                                               op: fn(T, T) -> T) -> Result<Vec<Box<Any>>, Error> {
+    if let Some(axis) = broadcast::incompatible_axis(shape_a, shape_b) {
+      return Err(Error::Buffer(buffer::Error::BroadcastError {
+        shape_a: shape_a.to_vec(),
+        shape_b: shape_b.to_vec(),
+        axis: axis
+      }))
+    }
+
+    let ar = try!(a.exec(ctx));
+    let br = try!(b.exec(ctx));
+    let shape_a = shape_a.to_vec();
+    let shape_b = shape_b.to_vec();
+    let dev = backend.device().clone();
+
+    let inputs = ar.join(br).map_err(|e| (*e).clone()).and_then(|(a, b)| {
+      a.lock().join(b.lock())
+    });
+    let rf: Box<Future<Item=(Buffer<usize>, Buffer<T>),Error=buffer::Error>> =
+      Box::new(inputs.and_then(move |(a, b)| {
+        let pool = dev.pool().clone();
+        a.sync(&dev).join(b.sync(&dev)).and_then(move |(a, b)| {
+          pool.spawn_fn(move || {
+            let n_a: &[T] = try!(try!(a.native_memory(&dev)).try_as_slice());
+            let n_b: &[T] = try!(try!(b.native_memory(&dev)).try_as_slice());
+
+            let (bshape, iter_a, iter_b) = try!(broadcast::try_new_broadcast(&shape_a, n_a, &shape_b, n_b, 0));
+
+            let mut c = try!(try!(Buffer::with_capacity_native(&dev, bshape.iter().product())).try_lock());
+            let shape_c = try!(Buffer::from_vec_native(&dev, bshape));
+
+            {
+              let n_c: &mut [T] = try!(try!(c.native_memory_mut(&dev)).try_as_mut_slice());
+
+              for ((x, y), v) in iter_a.zip(iter_b).zip(n_c.iter_mut()) {
+                *v = op(x[0], y[0]);
+              }
+            }
+
+            Ok((shape_c, c.into()))
+          })
+        })
+      }));
+    let rf = rf.shared();
+
+    let c: Box<Future<Item=Buffer<T>,Error=buffer::Error>> =
+      Box::new(rf.clone().map(|r| r.1.clone()).map_err(|e| (*e).clone()));
+    let sc: Box<Future<Item=Buffer<usize>,Error=buffer::Error>> =
+      Box::new(rf.map(|r| r.0.clone()).map_err(|e| (*e).clone()));
+
+    Ok(vec![Box::new(c.shared()) as Box<Any>,
+            Box::new(sc.shared()) as Box<Any>])
+  }
+}
@@ -1,7 +1,17 @@
 pub mod placeholder;
+pub mod constant;
 pub mod dot;
+pub mod add;
+pub mod mul;
+
+mod elementwise;
 
 pub use self::placeholder::Placeholder;
+pub use self::constant::Constant;
 
 #[cfg(feature = "native")]
-pub use self::dot::native;
+pub mod native {
+  pub use super::dot::native::*;
+  pub use super::add::native::*;
+  pub use super::mul::native::*;
+}
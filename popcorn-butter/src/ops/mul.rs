@@ -0,0 +1,79 @@
+use uuid::Uuid;
+use exec::*;
+use ops::elementwise;
+
+#[cfg(feature = "native")]
+pub mod native {
+  use super::*;
+  use popcorn::native;
+  use popcorn::num::Numeric;
+
+  pub struct Mul<T> {
+    uid: Uuid,
+    shape_a: Vec<usize>,
+    a: Socket<T>,
+    shape_b: Vec<usize>,
+    b: Socket<T>,
+    backend: native::Backend
+  }
+
+  impl<T> Mul<T> {
+    pub fn new(shape_a: Vec<usize>,
+               a: Socket<T>,
+               shape_b: Vec<usize>,
+               b: Socket<T>,
+               backend: native::Backend) -> Mul<T> {
+      Mul {
+        uid: Uuid::new_v4(),
+        shape_a: shape_a,
+        a: a,
+        shape_b: shape_b,
+        b: b,
+        backend: backend
+      }
+    }
+  }
+
+  /// Outputs are the elementwise product at index 0 and its shape at index 1.
+  impl<T: Numeric + Sync + Send + 'static> Executable for Mul<T> {
+    fn uid(&self) -> &Uuid { &self.uid }
+
+    fn exec<'a>(&self, ctx: &'a mut Context) ->
+      Result<Vec<Box<Any>>, Error> {
+        elementwise::native::exec(&self.shape_a, &self.a, &self.shape_b, &self.b,
+                                  &self.backend, ctx, mul::<T>)
+      }
+  }
+
+  fn mul<T: Numeric>(x: T, y: T) -> T { x * y }
+
+  #[cfg(test)]
+  mod test {
+    use super::*;
+    use ops::Placeholder;
+    use popcorn::Backend;
+    use futures::Future;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_mul_broadcasts_scalar() {
+      let backend = native::Backend::default();
+      let a = Buffer::from_vec_native(backend.device(), vec![2.0f32]).unwrap();
+      let b = Buffer::from_vec_native(backend.device(), vec![1.0f32, 2.0, 3.0]).unwrap();
+
+      let pa = Arc::new(Placeholder::<f32>::new());
+      let pb = Arc::new(Placeholder::<f32>::new());
+      let mul = Arc::new(Mul::new(vec![], Socket::<f32>::new(pa.clone(), 0),
+                                  vec![3], Socket::<f32>::new(pb.clone(), 0),
+                                  backend));
+
+      let mut ctx = Context::new();
+      ctx.set_input(pa.uid().clone(), Ok(a)).set_input(pb.uid().clone(), Ok(b));
+
+      let rv = Socket::<f32>::new(mul, 0).exec(&mut ctx).unwrap().
+        map_err(|e| (*e).clone()).
+        and_then(|b| b.lock().and_then(|b| b.sync_to_vec())).wait().unwrap();
+      assert_eq!(rv, vec![2.0, 4.0, 6.0]);
+    }
+  }
+}
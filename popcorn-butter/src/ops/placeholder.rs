@@ -46,7 +46,7 @@ mod test {
     let socket = Socket::<f32>::new(p.clone() as Arc<Executable>, 0);
 
     let rv = socket.exec(&mut ctx).unwrap().
-      map_err(|se| se.clone()).
+      map_err(|se| (*se).clone()).
       and_then(|b| {
         b.lock().and_then(|b| b.sync_to_vec())
       }).map(|v| v).wait().unwrap();
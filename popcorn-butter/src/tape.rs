@@ -0,0 +1,239 @@
+//! Reverse-mode differentiation of butter graphs.
+//!
+//! Operations built through a `Tape` are recorded along with their
+//! input sockets and shapes. `Tape::backward` walks the recording in
+//! reverse and builds a second graph of executables computing the
+//! gradient of a scalar output with respect to every recorded input.
+//! The gradient graph is evaluated with the same `Context` as the
+//! forward graph, so forward results are shared rather than recomputed.
+//!
+//! Only inputs of identical shapes are supported for now, a gradient
+//! through a broadcast would need a reduction back to the input shape.
+
+#[cfg(feature = "native")]
+pub use self::native::*;
+
+#[cfg(feature = "native")]
+mod native {
+  use std::collections::HashMap;
+  use std::fmt;
+  use std::sync::Arc;
+  use uuid::Uuid;
+  use popcorn::Backend;
+  use popcorn::native;
+  use popcorn::num::Numeric;
+  use popcorn_blas::frameworks::native::core_ops;
+  use exec::*;
+  use ops::Constant;
+  use ops::native::{Dot, Add, Mul};
+
+  enum Rule<T> {
+    Dot { shape_a: Vec<usize>, a: Socket<T>, shape_b: Vec<usize>, b: Socket<T> },
+    Add { a: Socket<T>, b: Socket<T> }
+  }
+
+  struct Node<T> {
+    output: Socket<T>,
+    shape: Vec<usize>,
+    rule: Rule<T>
+  }
+
+  pub struct Tape<T> {
+    backend: native::Backend,
+    nodes: Vec<Node<T>>
+  }
+
+  /// Gradient sockets, keyed by the input socket they belong to.
+  pub struct Gradients<T> {
+    grads: HashMap<(Uuid, usize), Socket<T>>
+  }
+
+  fn key<T: 'static>(socket: &Socket<T>) -> (Uuid, usize) {
+    (socket.uid().clone(), socket.index())
+  }
+
+  impl<T: core_ops::Dot + Numeric + fmt::Debug + Sync + Send + 'static> Tape<T> {
+    pub fn new(backend: native::Backend) -> Tape<T> {
+      Tape {
+        backend: backend,
+        nodes: Vec::new()
+      }
+    }
+
+    /// Record a dot product of `a` and `b` over their trailing dimension.
+    pub fn dot(&mut self,
+               shape_a: Vec<usize>,
+               a: Socket<T>,
+               shape_b: Vec<usize>,
+               b: Socket<T>) -> Result<Socket<T>, Error> {
+      if shape_a != shape_b || shape_a.is_empty() {
+        return Err(Error::NotDifferentiable)
+      }
+
+      let dot = try!(Dot::new(shape_a.clone(), a.clone(),
+                              shape_b.clone(), b.clone(),
+                              self.backend.clone()).map_err(Error::Buffer));
+      let output = Socket::new(Arc::new(dot), 0);
+      let shape = shape_a[..shape_a.len() - 1].to_vec();
+
+      self.nodes.push(Node {
+        output: output.clone(),
+        shape: shape,
+        rule: Rule::Dot { shape_a: shape_a, a: a, shape_b: shape_b, b: b }
+      });
+      Ok(output)
+    }
+
+    /// Record an elementwise sum of `a` and `b`.
+    pub fn add(&mut self,
+               shape_a: Vec<usize>,
+               a: Socket<T>,
+               shape_b: Vec<usize>,
+               b: Socket<T>) -> Result<Socket<T>, Error> {
+      if shape_a != shape_b {
+        return Err(Error::NotDifferentiable)
+      }
+
+      let add = Add::new(shape_a.clone(), a.clone(), shape_b, b.clone(), self.backend.clone());
+      let output = Socket::new(Arc::new(add), 0);
+
+      self.nodes.push(Node {
+        output: output.clone(),
+        shape: shape_a,
+        rule: Rule::Add { a: a, b: b }
+      });
+      Ok(output)
+    }
+
+    /// Build the gradient graph of the scalar `output` of a recorded
+    /// operation.
+    pub fn backward(&self, output: &Socket<T>) -> Result<Gradients<T>, Error> {
+      let last = try!(self.nodes.iter().rposition(|n| key(&n.output) == key(output)).
+                      ok_or(Error::NoSuchElement));
+      if self.nodes[last].shape.iter().product::<usize>() != 1 {
+        return Err(Error::NotDifferentiable)
+      }
+
+      let one = try!(Buffer::from_vec_native(self.backend.device(), vec![T::one()]).map_err(Error::Buffer));
+      let mut grads = Gradients { grads: HashMap::new() };
+      grads.grads.insert(key(output), Socket::new(Arc::new(Constant::new(one)), 0));
+
+      for node in self.nodes[..last + 1].iter().rev() {
+        let g = match grads.grads.get(&key(&node.output)) {
+          Some(g) => g.clone(),
+          None => continue
+        };
+
+        match node.rule {
+          Rule::Dot { ref shape_a, ref a, ref shape_b, ref b } => {
+            // d(a·b)/da = g * b, with g stretched over the reduced dimension
+            let mut shape_g = node.shape.clone();
+            shape_g.push(1);
+
+            let ga = self.mul(shape_g.clone(), g.clone(), shape_b.clone(), b.clone());
+            let gb = self.mul(shape_g, g, shape_a.clone(), a.clone());
+            self.accumulate(&mut grads, a, ga, shape_a);
+            self.accumulate(&mut grads, b, gb, shape_b);
+          },
+          Rule::Add { ref a, ref b } => {
+            self.accumulate(&mut grads, a, g.clone(), &node.shape);
+            self.accumulate(&mut grads, b, g, &node.shape);
+          }
+        }
+      }
+
+      Ok(grads)
+    }
+
+    fn mul(&self, shape_a: Vec<usize>, a: Socket<T>, shape_b: Vec<usize>, b: Socket<T>) -> Socket<T> {
+      Socket::new(Arc::new(Mul::new(shape_a, a, shape_b, b, self.backend.clone())), 0)
+    }
+
+    // An input used more than once sums the gradients of all its uses
+    fn accumulate(&self, grads: &mut Gradients<T>, input: &Socket<T>, g: Socket<T>, shape: &[usize]) {
+      let k = key(input);
+      let g = match grads.grads.remove(&k) {
+        Some(prev) => {
+          let add = Add::new(shape.to_vec(), prev, shape.to_vec(), g, self.backend.clone());
+          Socket::new(Arc::new(add), 0)
+        },
+        None => g
+      };
+      grads.grads.insert(k, g);
+    }
+  }
+
+  impl<T: 'static> Gradients<T> {
+    /// The gradient with respect to `input`, if the output depends on it.
+    pub fn wrt(&self, input: &Socket<T>) -> Option<&Socket<T>> {
+      self.grads.get(&key(input))
+    }
+  }
+
+  #[cfg(test)]
+  mod test {
+    use super::*;
+    use ops::Placeholder;
+    use futures::Future;
+
+    fn read(socket: &Socket<f32>, ctx: &mut Context) -> Vec<f32> {
+      socket.exec(ctx).unwrap().
+        map_err(|e| (*e).clone()).
+        and_then(|b| b.lock().and_then(|b| b.sync_to_vec())).wait().unwrap()
+    }
+
+    #[test]
+    fn test_dot_gradient() {
+      let backend = native::Backend::default();
+      let px = Arc::new(Placeholder::<f32>::new());
+      let py = Arc::new(Placeholder::<f32>::new());
+      let x = Socket::<f32>::new(px.clone(), 0);
+      let y = Socket::<f32>::new(py.clone(), 0);
+
+      let mut tape = Tape::new(backend.clone());
+      let z = tape.dot(vec![3], x.clone(), vec![3], y.clone()).unwrap();
+      let grads = tape.backward(&z).unwrap();
+
+      let mut ctx = Context::new();
+      ctx.set_input(px.uid().clone(), Buffer::from_vec_native(backend.device(), vec![1.0f32, 2.0, 3.0])).
+        set_input(py.uid().clone(), Buffer::from_vec_native(backend.device(), vec![4.0f32, 5.0, 6.0]));
+
+      assert_eq!(read(&z, &mut ctx), vec![32.0]);
+      assert_eq!(read(grads.wrt(&x).unwrap(), &mut ctx), vec![4.0, 5.0, 6.0]);
+      assert_eq!(read(grads.wrt(&y).unwrap(), &mut ctx), vec![1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn test_gradient_accumulates() {
+      let backend = native::Backend::default();
+      let px = Arc::new(Placeholder::<f32>::new());
+      let py = Arc::new(Placeholder::<f32>::new());
+      let x = Socket::<f32>::new(px.clone(), 0);
+      let y = Socket::<f32>::new(py.clone(), 0);
+
+      // z = (x + y)·x, so dz/dx = 2x + y and dz/dy = x
+      let mut tape = Tape::new(backend.clone());
+      let s = tape.add(vec![3], x.clone(), vec![3], y.clone()).unwrap();
+      let z = tape.dot(vec![3], s, vec![3], x.clone()).unwrap();
+      let grads = tape.backward(&z).unwrap();
+
+      let mut ctx = Context::new();
+      ctx.set_input(px.uid().clone(), Buffer::from_vec_native(backend.device(), vec![1.0f32, 2.0, 3.0])).
+        set_input(py.uid().clone(), Buffer::from_vec_native(backend.device(), vec![4.0f32, 5.0, 6.0]));
+
+      assert_eq!(read(grads.wrt(&x).unwrap(), &mut ctx), vec![6.0, 9.0, 12.0]);
+      assert_eq!(read(grads.wrt(&y).unwrap(), &mut ctx), vec![1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn test_backward_needs_scalar_output() {
+      let backend = native::Backend::default();
+      let px = Arc::new(Placeholder::<f32>::new());
+      let x = Socket::<f32>::new(px.clone(), 0);
+
+      let mut tape = Tape::new(backend);
+      let s = tape.add(vec![3], x.clone(), vec![3], x.clone()).unwrap();
+      assert!(match tape.backward(&s) { Err(Error::NotDifferentiable) => true, _ => false });
+    }
+  }
+}
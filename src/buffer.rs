@@ -1,17 +1,40 @@
-use futures::{Future, IntoFuture};
+use futures::{future, Future, IntoFuture};
 use std::marker::PhantomData;
 use std::mem;
+use std::cmp;
+use std::slice;
 use std::collections::HashMap;
-use std::ops::{Deref, DerefMut};
-use device::Device;
+use std::error;
+use std::fmt;
+use std::io;
+use std::f64;
+use std::ops::{Deref, DerefMut, Range};
+use device::{Device, DeviceCapabilities};
+#[cfg(feature = "native")]
+use num::{One, Numeric};
 use vault::{Vault, VaultAcquired};
 
 use frameworks::native;
+#[cfg(feature = "cuda")]
+use frameworks::cuda;
+use futures_cpupool::CpuPool;
+#[cfg(feature = "native")]
+use rand::{Rng, SeedableRng};
+#[cfg(feature = "native")]
+use rand::rngs::SmallRng;
+#[cfg(feature = "bytemuck")]
+use bytemuck;
+#[cfg(feature = "half")]
+use half::f16;
+#[cfg(feature = "ndarray")]
+use ndarray::{ArrayD, ArrayView, Dimension, IxDyn};
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum BufferDevice {
   #[cfg(feature = "native")]
-  Native(native::Device)
+  Native(native::Device),
+  #[cfg(feature = "cuda")]
+  Cuda(cuda::Device)
 }
 
 #[cfg(feature = "native")]
@@ -32,18 +55,37 @@ impl From<native::Memory> for BufferMemory {
 #[derive(Debug)]
 pub enum BufferMemory {
   #[cfg(feature = "native")]
-  Native(native::Memory)
+  Native(native::Memory),
+  #[cfg(feature = "cuda")]
+  Cuda(cuda::Memory)
 }
 
 #[derive(Debug, Clone)]
 pub enum Error {
   #[cfg(feature = "native")]
   Native(native::Error),
+  #[cfg(feature = "cuda")]
+  Cuda(cuda::Error),
 
   InvalidLock,
   InvalidRawBuffer,
   InvalidDevice,
-  InvalidBroadcast
+  InvalidBroadcast,
+  BroadcastError { shape_a: Vec<usize>, shape_b: Vec<usize>, axis: usize },
+  OutOfBounds,
+  AllocationTooLarge { bytes: usize, max: usize },
+  AllocationOverflow { size: usize, element: usize },
+  SizeMismatch { expected: usize, actual: usize },
+  InvalidElementSize { bytes: usize, element: usize },
+  InvalidAlignment { align: usize },
+  InvalidStep,
+  EmptyBuffer,
+  DivisionByZero,
+  InvalidRange,
+  InvalidShape,
+  LatestCopy,
+  Cancelled,
+  SharedBuffer
 }
 
 #[cfg(feature = "native")]
@@ -51,7 +93,163 @@ impl From<native::Error> for Error {
   fn from(err: native::Error) -> Error { Error::Native(err) }
 }
 
-#[derive(Debug)]
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      #[cfg(feature = "native")]
+      Error::Native(ref err) => write!(f, "native device error: {}", err),
+      #[cfg(feature = "cuda")]
+      Error::Cuda(ref err) => write!(f, "cuda device error: {}", err),
+      Error::InvalidLock => write!(f, "buffer could not be locked"),
+      Error::InvalidRawBuffer => write!(f, "buffer contents are inconsistent"),
+      Error::InvalidDevice => write!(f, "buffer has no memory on the device"),
+      Error::InvalidBroadcast => write!(f, "shapes cannot be broadcast together"),
+      Error::BroadcastError { ref shape_a, ref shape_b, axis } => {
+        write!(f, "shapes {:?} and {:?} cannot be broadcast together on axis {}", shape_a, shape_b, axis)
+      },
+      Error::OutOfBounds => write!(f, "index out of bounds"),
+      Error::AllocationTooLarge { bytes, max } => {
+        write!(f, "allocation of {} bytes exceeds the device limit of {} bytes", bytes, max)
+      },
+      Error::AllocationOverflow { size, element } => {
+        write!(f, "allocation of {} elements of {} bytes overflows", size, element)
+      },
+      Error::SizeMismatch { expected, actual } => {
+        write!(f, "expected a buffer of {} elements, got {}", expected, actual)
+      },
+      Error::InvalidElementSize { bytes, element } => {
+        write!(f, "{} bytes is not a whole number of {} byte elements", bytes, element)
+      },
+      Error::InvalidAlignment { align } => write!(f, "memory is not aligned to {} bytes", align),
+      Error::InvalidStep => write!(f, "sequence step must not be zero"),
+      Error::EmptyBuffer => write!(f, "buffer has no elements"),
+      Error::DivisionByZero => write!(f, "integer division by zero"),
+      Error::InvalidRange => write!(f, "range minimum is greater than its maximum"),
+      Error::InvalidShape => write!(f, "shape is not valid for the operation"),
+      Error::LatestCopy => write!(f, "the latest copy of a buffer cannot be evicted"),
+      Error::Cancelled => write!(f, "operation was cancelled"),
+      Error::SharedBuffer => write!(f, "buffer has other handles")
+    }
+  }
+}
+
+impl error::Error for Error {
+  fn source(&self) -> Option<&(error::Error + 'static)> {
+    match *self {
+      #[cfg(feature = "native")]
+      Error::Native(ref err) => Some(err),
+      #[cfg(feature = "cuda")]
+      Error::Cuda(ref err) => Some(err),
+      _ => None
+    }
+  }
+}
+
+/// Keeps the buffer error as the source, with the closest kind.
+impl From<Error> for io::Error {
+  fn from(err: Error) -> io::Error {
+    let kind = match err {
+      #[cfg(feature = "native")]
+      Error::Native(_) => io::ErrorKind::Other,
+      #[cfg(feature = "cuda")]
+      Error::Cuda(_) => io::ErrorKind::Other,
+      Error::InvalidLock => io::ErrorKind::WouldBlock,
+      Error::InvalidRawBuffer => io::ErrorKind::InvalidData,
+      Error::InvalidDevice => io::ErrorKind::NotFound,
+      Error::AllocationTooLarge { .. } | Error::AllocationOverflow { .. } => io::ErrorKind::OutOfMemory,
+      Error::Cancelled => io::ErrorKind::Interrupted,
+      Error::InvalidBroadcast |
+      Error::BroadcastError { .. } |
+      Error::OutOfBounds |
+      Error::SizeMismatch { .. } |
+      Error::InvalidElementSize { .. } |
+      Error::InvalidAlignment { .. } |
+      Error::InvalidStep |
+      Error::EmptyBuffer |
+      Error::DivisionByZero |
+      Error::InvalidRange |
+      Error::InvalidShape |
+      Error::LatestCopy |
+      Error::SharedBuffer => io::ErrorKind::InvalidInput
+    };
+
+    io::Error::new(kind, err)
+  }
+}
+
+#[cfg(feature = "cuda")]
+impl From<cuda::Device> for BufferDevice {
+  fn from(dev: cuda::Device) -> BufferDevice { BufferDevice::Cuda(dev) }
+}
+
+#[cfg(feature = "cuda")]
+impl<'a> From<&'a cuda::Device> for BufferDevice {
+  fn from(dev: &'a cuda::Device) -> BufferDevice { BufferDevice::Cuda(dev.clone()) }
+}
+
+#[cfg(feature = "cuda")]
+impl From<cuda::Memory> for BufferMemory {
+  fn from(mem: cuda::Memory) -> BufferMemory { BufferMemory::Cuda(mem) }
+}
+
+#[cfg(feature = "cuda")]
+impl From<cuda::Error> for Error {
+  fn from(err: cuda::Error) -> Error { Error::Cuda(err) }
+}
+
+impl BufferDevice {
+  pub fn capabilities(&self) -> DeviceCapabilities {
+    match *self {
+      #[cfg(feature = "native")]
+      BufferDevice::Native(ref dev) => dev.capabilities(),
+      #[cfg(feature = "cuda")]
+      BufferDevice::Cuda(ref dev) => dev.capabilities()
+    }
+  }
+
+  fn pool(&self) -> &CpuPool {
+    match *self {
+      #[cfg(feature = "native")]
+      BufferDevice::Native(ref dev) => dev.pool(),
+      #[cfg(feature = "cuda")]
+      BufferDevice::Cuda(ref dev) => dev.pool()
+    }
+  }
+
+  fn check_allocation(&self, bytes: usize) -> Result<(), Error> {
+    let max = self.capabilities().max_single_allocation;
+    if bytes > max {
+      Err(Error::AllocationTooLarge { bytes: bytes, max: max })
+    } else {
+      Ok(())
+    }
+  }
+}
+
+#[cfg(feature = "cuda")]
+impl BufferMemory {
+  fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+    match *self {
+      #[cfg(feature = "native")]
+      BufferMemory::Native(ref nm) => nm.try_as_slice().map(|b: &[u8]| b.to_vec()).map_err(Error::Native),
+      BufferMemory::Cuda(ref cm) => cm.to_vec().map_err(Error::Cuda)
+    }
+  }
+
+  fn copy_from_bytes(&mut self, bytes: &[u8]) -> Result<(), Error> {
+    match *self {
+      #[cfg(feature = "native")]
+      BufferMemory::Native(ref mut nm) => nm.copy_from(bytes).map_err(Error::Native),
+      BufferMemory::Cuda(ref mut cm) => cm.copy_from(bytes).map_err(Error::Cuda)
+    }
+  }
+}
+
+/// Identifies a buffer, shared by its clones but not by copies of its
+/// contents. Ids are ordered by creation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BufferId(usize);
+
 pub struct LockedBuffer<T> {
   raw: VaultAcquired<RawBuffer<T>>
 }
@@ -70,6 +268,79 @@ pub struct RawBuffer<T> {
   _pd: PhantomData<T>,
 }
 
+#[cfg(feature = "serialize")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BufferSnapshot<T> {
+  pub size: usize,
+  pub data: Vec<T>
+}
+
+#[cfg(feature = "native")]
+#[derive(Debug)]
+pub struct BufferView<'a, T> {
+  mem: &'a native::Memory,
+  range: Range<usize>,
+
+  _pd: PhantomData<T>,
+}
+
+#[cfg(feature = "native")]
+#[derive(Debug)]
+pub struct BufferViewMut<'a, T> {
+  mem: &'a mut native::Memory,
+  range: Range<usize>,
+
+  _pd: PhantomData<T>,
+}
+
+// Elements shown at each end of a buffer by `Debug`, the rest is elided
+const DEBUG_EDGE: usize = 4;
+
+// Debug formats a slice, eliding the middle of long ones
+struct Elided<'a, T: 'a>(&'a [T]);
+
+struct Ellipsis;
+
+impl fmt::Debug for Ellipsis {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "...")
+  }
+}
+
+impl<'a, T: fmt::Debug> fmt::Debug for Elided<'a, T> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let vs = self.0;
+    if vs.len() <= 2 * DEBUG_EDGE {
+      f.debug_list().entries(vs.iter()).finish()
+    } else {
+      f.debug_list().
+        entries(vs[..DEBUG_EDGE].iter()).
+        entry(&Ellipsis).
+        entries(vs[vs.len() - DEBUG_EDGE..].iter()).
+        finish()
+    }
+  }
+}
+
+/// Shows the latest contents when they are on a native device.
+impl<T: fmt::Debug + Copy + 'static> fmt::Debug for LockedBuffer<T> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let mut s = f.debug_struct("LockedBuffer");
+    s.field("size", &self.size).field("latest_device", &self.latest_device);
+
+    #[cfg(feature = "native")]
+    {
+      if let Some(&BufferMemory::Native(ref mem)) = self.copies.get(&self.latest_device) {
+        if let Ok(vs) = mem.try_as_slice::<T>() {
+          s.field("data", &Elided(vs));
+        }
+      }
+    }
+
+    s.finish()
+  }
+}
+
 impl<T> Deref for LockedBuffer<T> {
   type Target = RawBuffer<T>;
 
@@ -84,7 +355,53 @@ impl<T: Send + Copy + Sized + 'static> RawBuffer<T> {
   pub fn new<D: Into<BufferDevice>>(dev: D, size: usize) -> Result<RawBuffer<T>, Error> {
     let bdev: BufferDevice = dev.into();
     let mut copies = HashMap::new();
-    let copy = try!(Self::alloc_on_device(&bdev, size * mem::size_of::<T>()));
+    let copy = try!(Self::alloc_on_device(&bdev, try!(Self::byte_size(size))));
+    copies.insert(bdev.clone(), copy);
+
+    Ok(RawBuffer {
+      size: size,
+      copies: copies,
+      latest_device: bdev,
+      _pd: PhantomData
+    })
+  }
+
+  pub fn new_aligned<D: Into<BufferDevice>>(dev: D, size: usize, align: usize) -> Result<RawBuffer<T>, Error> {
+    let bdev: BufferDevice = dev.into();
+    let bytes = try!(Self::byte_size(size));
+    try!(bdev.check_allocation(bytes));
+    let mut copies = HashMap::new();
+    let copy = match bdev {
+      #[cfg(feature = "native")]
+      BufferDevice::Native(ref dev_n) => {
+        try!(dev_n.alloc_memory_aligned(bytes, align).map(|m| BufferMemory::Native(m.tagged::<T>())))
+      },
+      // Device allocations are always aligned to at least 256 bytes
+      #[cfg(feature = "cuda")]
+      BufferDevice::Cuda(_) => {
+        if !align.is_power_of_two() || align > 256 {
+          return Err(Error::InvalidAlignment { align: align })
+        }
+        try!(Self::alloc_on_device(&bdev, bytes))
+      },
+    };
+    copies.insert(bdev.clone(), copy);
+
+    Ok(RawBuffer {
+      size: size,
+      copies: copies,
+      latest_device: bdev,
+      _pd: PhantomData
+    })
+  }
+
+  #[cfg(feature = "native")]
+  pub fn new_pinned(dev: &native::Device, size: usize) -> Result<RawBuffer<T>, Error> {
+    let bdev: BufferDevice = dev.into();
+    let bytes = try!(Self::byte_size(size));
+    try!(bdev.check_allocation(bytes));
+    let mut copies = HashMap::new();
+    let copy = BufferMemory::Native(try!(dev.alloc_pinned(bytes)).tagged::<T>());
     copies.insert(bdev.clone(), copy);
 
     Ok(RawBuffer {
@@ -99,7 +416,7 @@ impl<T: Send + Copy + Sized + 'static> RawBuffer<T> {
   pub fn from_vec_native(dev: &native::Device, vec: Vec<T>) -> Result<RawBuffer<T>, Error> {
     let bdev: BufferDevice = dev.into();
     let mut copies = HashMap::new();
-    let mut mem = try!(dev.alloc_memory(vec.len() * mem::size_of::<T>()));
+    let mut mem = try!(dev.alloc_memory(vec.len() * mem::size_of::<T>())).tagged::<T>();
     try!(mem.copy_from(&vec));
     let copy = BufferMemory::Native(mem);
     copies.insert(bdev.clone(), copy);
@@ -112,103 +429,822 @@ impl<T: Send + Copy + Sized + 'static> RawBuffer<T> {
     })
   }
 
+  /// Wrap existing memory on `dev`, which must hold exactly `size`
+  /// elements.
+  #[cfg(feature = "native")]
+  pub fn from_memory_native(dev: &native::Device, size: usize, mem: native::Memory) -> Result<RawBuffer<T>, Error> {
+    let bytes = try!(Self::byte_size(size));
+    if mem.len() != bytes {
+      return Err(Error::SizeMismatch { expected: bytes, actual: mem.len() })
+    }
+
+    let bdev: BufferDevice = dev.into();
+    let mut copies = HashMap::new();
+    copies.insert(bdev.clone(), BufferMemory::Native(mem.tagged::<T>()));
+
+    Ok(RawBuffer {
+      size: size,
+      copies: copies,
+      latest_device: bdev,
+      _pd: PhantomData
+    })
+  }
+
+  /// Take the memory of the copy on `dev`, dropping all others. Its
+  /// contents may be stale.
+  #[cfg(feature = "native")]
+  pub fn into_native_memory(mut self, dev: &native::Device) -> Option<native::Memory> {
+    match self.copies.remove(&BufferDevice::Native(dev.clone())) {
+      Some(BufferMemory::Native(nm)) => Some(nm),
+      _ => None
+    }
+  }
+
+  fn byte_size(size: usize) -> Result<usize, Error> {
+    size.checked_mul(mem::size_of::<T>()).ok_or(Error::AllocationOverflow {
+      size: size,
+      element: mem::size_of::<T>()
+    })
+  }
+
   fn alloc_on_device(dev: &BufferDevice, size: usize) -> Result<BufferMemory, Error> {
+    try!(dev.check_allocation(size));
     match *dev {
       #[cfg(feature = "native")]
       BufferDevice::Native(ref dev_n) => Self::alloc_on_device_native(dev_n, size),
+      #[cfg(feature = "cuda")]
+      BufferDevice::Cuda(ref dev_c) => {
+        dev_c.alloc_memory(size).
+          map(BufferMemory::Cuda).
+          map_err(Error::Cuda)
+      },
     }
   }
 
   fn alloc_on_device_native(dev: &native::Device, size: usize) -> Result<BufferMemory, Error> {
     dev.alloc_memory(size).
-      map(|m| BufferMemory::Native(m)).
+      map(|m| BufferMemory::Native(m.tagged::<T>())).
       map_err(|e| Error::Native(e))
   }
 
   #[cfg(feature = "native")]
   pub fn native_memory(&self, dev: &native::Device) -> Result<&native::Memory, Error> {
     match self.copies.get(&BufferDevice::Native(dev.clone())) {
-      Some(mem) => {
-        let BufferMemory::Native(ref nm) = *mem;
-        Ok(nm)
-      },
-      None => Err(Error::InvalidDevice)
+      Some(&BufferMemory::Native(ref nm)) => Ok(nm),
+      _ => Err(Error::InvalidDevice)
     }
   }
 
   #[cfg(feature = "native")]
   pub fn native_memory_mut(&mut self, dev: &native::Device) -> Result<&mut native::Memory, Error> {
-    match self.copies.get_mut(&BufferDevice::Native(dev.clone())) {
-      Some(mem) => {
-        let BufferMemory::Native(ref mut nm) = *mem;
-        Ok(nm)
-      },
-      None => Err(Error::InvalidDevice)
+    let bdev = BufferDevice::Native(dev.clone());
+    if self.copies.contains_key(&bdev) {
+      self.invalidate_copies(&bdev);
+    }
+
+    match self.copies.get_mut(&bdev) {
+      Some(&mut BufferMemory::Native(ref mut nm)) => Ok(nm),
+      _ => Err(Error::InvalidDevice)
+    }
+  }
+
+  #[cfg(feature = "native")]
+  pub fn get(&self, dev: &native::Device, idx: usize) -> Result<T, Error> {
+    let s: &[T] = try!(try!(self.native_memory(dev)).try_as_slice());
+    s.get(idx).map(|v| *v).ok_or(Error::OutOfBounds)
+  }
+
+  #[cfg(feature = "native")]
+  pub fn set(&mut self, dev: &native::Device, idx: usize, value: T) -> Result<(), Error> {
+    let s: &mut [T] = try!(try!(self.native_memory_mut(dev)).try_as_mut_slice());
+    match s.get_mut(idx) {
+      Some(v) => { *v = value; Ok(()) },
+      None => Err(Error::OutOfBounds)
     }
   }
 
+  #[cfg(feature = "native")]
+  pub fn iter(&self, dev: &native::Device) -> Result<slice::Iter<T>, Error> {
+    let s: &[T] = try!(try!(self.native_memory(dev)).try_as_slice());
+    Ok(s.iter())
+  }
+
+  #[cfg(feature = "native")]
+  pub fn iter_mut(&mut self, dev: &native::Device) -> Result<slice::IterMut<T>, Error> {
+    let s: &mut [T] = try!(try!(self.native_memory_mut(dev)).try_as_mut_slice());
+    Ok(s.iter_mut())
+  }
+
   pub fn size(&self) -> usize {
     self.size
   }
+
+  /// The device holding the most recent contents.
+  pub fn latest_device(&self) -> &BufferDevice {
+    &self.latest_device
+  }
+
+  /// Every device holding a copy of the most recent contents.
+  pub fn devices(&self) -> Vec<BufferDevice> {
+    self.copies.keys().cloned().collect()
+  }
+
+  // Make `dev` the latest device and drop all other copies, which
+  // are about to go stale. They are re-synced lazily when needed.
+  fn invalidate_copies(&mut self, dev: &BufferDevice) {
+    self.copies.retain(|d, _| d == dev);
+    self.latest_device = dev.clone();
+  }
+
+  fn zero(&mut self) -> Result<(), Error> {
+    for mem in self.copies.values_mut() {
+      match *mem {
+        #[cfg(feature = "native")]
+        BufferMemory::Native(ref mut nm) => nm.zero(),
+        #[cfg(feature = "cuda")]
+        BufferMemory::Cuda(ref mut cm) => try!(cm.zero()),
+      }
+    }
+
+    Ok(())
+  }
 }
 
 impl<T: Send + Copy + Sized + 'static> LockedBuffer<T> {
+  /// Free the copy on `dev`, which must not be the latest one. The
+  /// buffer stays readable from its other copies.
+  pub fn evict(&mut self, dev: &BufferDevice) -> Result<(), Error> {
+    if *dev == self.latest_device {
+      return Err(Error::LatestCopy)
+    }
+
+    match self.copies.remove(dev) {
+      Some(_) => Ok(()),
+      None => Err(Error::InvalidDevice)
+    }
+  }
+
+  /// Release the lock, handing back a handle to the buffer.
+  pub fn unlock(self) -> Buffer<T> {
+    self.into()
+  }
+
   pub fn sync_from_vec(mut self, vec: Vec<T>) -> Box<Future<Item=LockedBuffer<T>,Error=Error>> {
+    if vec.len() != self.size {
+      return Box::new(Err(Error::SizeMismatch { expected: self.size, actual: vec.len() }).into_future())
+    }
+
     let dev = self.latest_device.clone();
+    self.raw.invalidate_copies(&dev);
     let copy = self.copies.remove(&dev);
 
     match copy {
       Some(mem) => {
-        match dev {
+        match (dev, mem) {
           #[cfg(feature = "native")]
-          BufferDevice::Native(ref dev) => {
-            let BufferMemory::Native(m) = mem;
+          (BufferDevice::Native(ref dev), BufferMemory::Native(m)) => {
             let new_dev = BufferDevice::Native(dev.clone());
             Box::new(dev.sync_from_vec(m, vec).map(move |mem| {
               self.raw.copies.insert(new_dev, BufferMemory::Native(mem));
               self
             }).map_err(Error::Native))
           },
+          #[cfg(feature = "cuda")]
+          (BufferDevice::Cuda(ref dev), BufferMemory::Cuda(m)) => {
+            let new_dev = BufferDevice::Cuda(dev.clone());
+            Box::new(dev.sync_from_vec(m, vec).map(move |mem| {
+              self.raw.copies.insert(new_dev, BufferMemory::Cuda(mem));
+              self
+            }).map_err(Error::Cuda))
+          },
+          #[cfg(all(feature = "native", feature = "cuda"))]
+          _ => Box::new(Err(Error::InvalidDevice).into_future())
+        }
+      },
+      None => Box::new(Err(Error::InvalidDevice).into_future())
+    }
+  }
+
+  pub fn sync_from_vec_at(mut self, offset: usize, vec: Vec<T>) -> Box<Future<Item=LockedBuffer<T>,Error=Error>> {
+    if offset.checked_add(vec.len()).map_or(true, |end| end > self.size) {
+      return Box::new(Err(Error::OutOfBounds).into_future())
+    }
+
+    let dev = self.latest_device.clone();
+    self.raw.invalidate_copies(&dev);
+    let copy = self.copies.remove(&dev);
+
+    match copy {
+      Some(mem) => {
+        match (dev, mem) {
+          #[cfg(feature = "native")]
+          (BufferDevice::Native(ref dev), BufferMemory::Native(m)) => {
+            let new_dev = BufferDevice::Native(dev.clone());
+            Box::new(dev.sync_from_vec_at(m, offset, vec).map(move |mem| {
+              self.raw.copies.insert(new_dev, BufferMemory::Native(mem));
+              self
+            }).map_err(Error::Native))
+          },
+          #[cfg(feature = "cuda")]
+          (BufferDevice::Cuda(ref dev), BufferMemory::Cuda(m)) => {
+            let new_dev = BufferDevice::Cuda(dev.clone());
+            Box::new(dev.sync_from_vec_at(m, offset, vec).map(move |mem| {
+              self.raw.copies.insert(new_dev, BufferMemory::Cuda(mem));
+              self
+            }).map_err(Error::Cuda))
+          },
+          #[cfg(all(feature = "native", feature = "cuda"))]
+          _ => Box::new(Err(Error::InvalidDevice).into_future())
         }
       },
       None => Box::new(Err(Error::InvalidDevice).into_future())
     }
   }
 
+  pub fn fill(mut self, value: T) -> Box<Future<Item=LockedBuffer<T>,Error=Error>> {
+    let dev = self.latest_device.clone();
+    self.raw.invalidate_copies(&dev);
+    let copy = self.copies.remove(&dev);
+
+    match copy {
+      Some(mem) => {
+        match (dev, mem) {
+          #[cfg(feature = "native")]
+          (BufferDevice::Native(ref dev), BufferMemory::Native(m)) => {
+            let new_dev = BufferDevice::Native(dev.clone());
+            Box::new(dev.fill(m, value).map(move |mem| {
+              self.raw.copies.insert(new_dev, BufferMemory::Native(mem));
+              self
+            }).map_err(Error::Native))
+          },
+          #[cfg(feature = "cuda")]
+          (BufferDevice::Cuda(ref dev), BufferMemory::Cuda(m)) => {
+            let new_dev = BufferDevice::Cuda(dev.clone());
+            Box::new(dev.fill(m, value).map(move |mem| {
+              self.raw.copies.insert(new_dev, BufferMemory::Cuda(mem));
+              self
+            }).map_err(Error::Cuda))
+          },
+          #[cfg(all(feature = "native", feature = "cuda"))]
+          _ => Box::new(Err(Error::InvalidDevice).into_future())
+        }
+      },
+      None => Box::new(Err(Error::InvalidDevice).into_future())
+    }
+  }
+
+  pub fn resize(mut self, new_size: usize, fill: T) -> Box<Future<Item=LockedBuffer<T>,Error=Error>> {
+    let dev = self.latest_device.clone();
+    let bytes = match RawBuffer::<T>::byte_size(new_size).and_then(|b| dev.check_allocation(b).map(|_| b)) {
+      Ok(bytes) => bytes,
+      Err(err) => return Box::new(Err(err).into_future())
+    };
+    let copy = self.copies.remove(&dev);
+
+    // Copies on other devices are stale once resized, they will be
+    // re-synced lazily from the latest device
+    self.copies.clear();
+
+    match copy {
+      Some(mem) => {
+        match (dev, mem) {
+          #[cfg(feature = "native")]
+          (BufferDevice::Native(ref dev), BufferMemory::Native(old)) => {
+            let new_dev = BufferDevice::Native(dev.clone());
+            let old_size = self.size;
+            let alloc_dev = dev.clone();
+            Box::new(dev.pool().spawn_fn(move || {
+              let mut new = try!(alloc_dev.alloc_memory(bytes)).tagged::<T>();
+              {
+                let src: &[T] = try!(old.try_as_slice());
+                let dst: &mut [T] = try!(new.try_as_mut_slice());
+                let n = cmp::min(old_size, new_size);
+
+                dst[..n].copy_from_slice(&src[..n]);
+                for v in dst[n..].iter_mut() {
+                  *v = fill;
+                }
+              }
+              Ok(new)
+            }).map(move |mem| {
+              self.raw.copies.insert(new_dev, BufferMemory::Native(mem));
+              self.raw.size = new_size;
+              self
+            }).map_err(Error::Native))
+          },
+          #[cfg(feature = "cuda")]
+          (BufferDevice::Cuda(ref dev), BufferMemory::Cuda(old)) => {
+            let new_dev = BufferDevice::Cuda(dev.clone());
+            let alloc_dev = dev.clone();
+            Box::new(dev.pool().spawn_fn(move || {
+              let mut vs: Vec<T> = try!(old.to_vec());
+              vs.resize(new_size, fill);
+
+              let mut new = try!(alloc_dev.alloc_memory(bytes));
+              try!(new.copy_from(&vs));
+              Ok(new)
+            }).map(move |mem| {
+              self.raw.copies.insert(new_dev, BufferMemory::Cuda(mem));
+              self.raw.size = new_size;
+              self
+            }).map_err(Error::Cuda))
+          },
+          #[cfg(all(feature = "native", feature = "cuda"))]
+          _ => Box::new(Err(Error::InvalidDevice).into_future())
+        }
+      },
+      None => Box::new(Err(Error::InvalidDevice).into_future())
+    }
+  }
+
+  /// Copy the latest contents into a new, independent buffer on `dev`.
+  /// The lock is handed back along with the copy.
+  pub fn deep_copy<D: Into<BufferDevice>>(mut self, dev: D) -> Box<Future<Item=(LockedBuffer<T>, Buffer<T>),Error=Error>> {
+    let bdev = dev.into();
+    let latest = self.latest_device.clone();
+
+    let mut raw = match RawBuffer::new(bdev.clone(), self.size) {
+      Ok(raw) => raw,
+      Err(e) => return Box::new(Err(e).into_future())
+    };
+    let src = match self.copies.remove(&latest) {
+      Some(mem) => mem,
+      None => return Box::new(Err(Error::InvalidDevice).into_future())
+    };
+
+    match (src, bdev) {
+      #[cfg(feature = "native")]
+      (BufferMemory::Native(src), BufferDevice::Native(dev)) => {
+        let dst_dev = BufferDevice::Native(dev.clone());
+        let mut dst = match raw.copies.remove(&dst_dev) {
+          Some(BufferMemory::Native(mem)) => mem,
+          _ => return Box::new(Err(Error::InvalidDevice).into_future())
+        };
+
+        Box::new(dev.pool().spawn_fn(move || {
+          try!(dst.copy_from(try!(src.try_as_slice::<u8>())));
+          Ok((src, dst))
+        }).map(move |(src, dst)| {
+          self.raw.copies.insert(latest, BufferMemory::Native(src));
+          raw.copies.insert(dst_dev, BufferMemory::Native(dst));
+          (self, raw.into())
+        }).map_err(Error::Native))
+      },
+      #[cfg(feature = "cuda")]
+      (src, bdev) => {
+        let copy = src.to_bytes().and_then(|bytes| {
+          try!(try!(raw.copies.get_mut(&bdev).ok_or(Error::InvalidDevice)).copy_from_bytes(&bytes));
+          Ok(raw.into())
+        });
+        self.raw.copies.insert(latest, src);
+
+        Box::new(copy.map(move |copy| (self, copy)).into_future())
+      },
+    }
+  }
+
+  /// View the same memory as elements of `U`, without copying.
+  ///
+  /// The memory moves into the new buffer, so this fails with
+  /// `SharedBuffer` unless this lock is the last reference to the
+  /// buffer: any other `Buffer` handle would be left empty.
+  pub fn reinterpret<U: Send + Copy + Sized + 'static>(mut self) -> Result<LockedBuffer<U>, Error> {
+    if !self.raw.is_unique() {
+      return Err(Error::SharedBuffer)
+    }
+
+    let bytes = self.size * mem::size_of::<T>();
+    let element = mem::size_of::<U>();
+    if element == 0 || bytes % element != 0 {
+      return Err(Error::InvalidElementSize { bytes: bytes, element: element })
+    }
+
+    let align = mem::align_of::<U>();
+    for copy in self.copies.values() {
+      match *copy {
+        #[cfg(feature = "native")]
+        BufferMemory::Native(ref nm) => {
+          if nm.as_ptr() as usize % align != 0 {
+            return Err(Error::InvalidAlignment { align: align })
+          }
+        },
+        #[cfg(feature = "cuda")]
+        BufferMemory::Cuda(ref cm) => {
+          if cm.as_device_ptr() as usize % align != 0 {
+            return Err(Error::InvalidAlignment { align: align })
+          }
+        },
+      }
+    }
+
+    #[cfg(feature = "native")]
+    for copy in self.copies.values_mut() {
+      if let BufferMemory::Native(ref mut nm) = *copy {
+        nm.tag::<U>();
+      }
+    }
+
+    // The memory moves into a new buffer, leaving this one empty, and
+    // nobody else can see it
+    let raw = RawBuffer {
+      size: bytes / element,
+      copies: mem::replace(&mut self.copies, HashMap::new()),
+      latest_device: self.latest_device.clone(),
+      _pd: PhantomData
+    };
+    self.raw.size = 0;
+
+    Buffer::from(raw).try_lock()
+  }
+
+  /// Copy the latest contents into the front of `dst`, keeping the
+  /// buffer locked. Elements of `dst` beyond `size()` are left untouched.
+  pub fn sync_to_slice(self, dst: &mut [T]) -> Box<Future<Item=LockedBuffer<T>,Error=Error>> {
+    if dst.len() < self.size {
+      return Box::new(Err(Error::OutOfBounds).into_future())
+    }
+
+    let copied = match self.copies.get(&self.latest_device) {
+      #[cfg(feature = "native")]
+      Some(&BufferMemory::Native(ref nm)) => {
+        nm.try_as_slice().map(|src: &[T]| {
+          dst[..src.len()].copy_from_slice(src);
+        }).map_err(Error::Native)
+      },
+      #[cfg(feature = "cuda")]
+      Some(&BufferMemory::Cuda(ref cm)) => cm.copy_to(&mut dst[..self.size]).map_err(Error::Cuda),
+      None => Err(Error::InvalidDevice)
+    };
+
+    Box::new(copied.map(|_| self).into_future())
+  }
+
+  #[cfg(feature = "serialize")]
+  pub fn snapshot(self) -> Box<Future<Item=BufferSnapshot<T>,Error=Error>> {
+    let size = self.size;
+    Box::new(self.sync_to_vec().map(move |data| BufferSnapshot {
+      size: size,
+      data: data
+    }))
+  }
+
+  /// The latest contents as an array of `shape`, which must hold as
+  /// many elements as the buffer.
+  #[cfg(feature = "ndarray")]
+  pub fn to_ndarray(self, shape: Vec<usize>) -> Box<Future<Item=ArrayD<T>,Error=Error>> {
+    let len = shape.iter().product();
+    if len != self.size {
+      return Box::new(Err(Error::SizeMismatch { expected: self.size, actual: len }).into_future())
+    }
+
+    Box::new(self.sync_to_vec().and_then(move |vec| {
+      ArrayD::from_shape_vec(IxDyn(&shape), vec).map_err(|_| Error::InvalidShape)
+    }))
+  }
+
   pub fn sync_to_vec(mut self) -> Box<Future<Item=Vec<T>,Error=Error>> {
     let dev = self.latest_device.clone();
     let copy = self.copies.remove(&dev);
     match copy {
       Some(mem) => {
-        match dev {
+        match (dev, mem) {
           #[cfg(feature = "native")]
-          BufferDevice::Native(ref dev) => {
-            let BufferMemory::Native(m) = mem;
+          (BufferDevice::Native(ref dev), BufferMemory::Native(m)) => {
             let new_dev = BufferDevice::Native(dev.clone());
             Box::new(dev.sync_to_vec(m).map(move |(mem, vec)| {
               self.copies.insert(new_dev, BufferMemory::Native(mem));
               vec
             }).map_err(Error::Native))
           },
+          #[cfg(feature = "cuda")]
+          (BufferDevice::Cuda(ref dev), BufferMemory::Cuda(m)) => {
+            let new_dev = BufferDevice::Cuda(dev.clone());
+            Box::new(dev.sync_to_vec(m).map(move |(mem, vec)| {
+              self.copies.insert(new_dev, BufferMemory::Cuda(mem));
+              vec
+            }).map_err(Error::Cuda))
+          },
+          #[cfg(all(feature = "native", feature = "cuda"))]
+          _ => Box::new(Err(Error::InvalidDevice).into_future())
         }
       },
       None => Box::new(Err(Error::InvalidDevice).into_future())
     }
   }
 
-  pub fn sync<D: Into<BufferDevice>>(self, dev: D) -> Box<Future<Item=LockedBuffer<T>,Error=Error>> {
+  pub fn sync<D: Into<BufferDevice>>(mut self, dev: D) -> Box<Future<Item=LockedBuffer<T>,Error=Error>> {
     let bdev = dev.into();
 
-    match self.latest_device {
+    // Nothing to do if the target already holds the latest copy
+    if bdev == self.latest_device {
+      return Box::new(Ok(self).into_future())
+    }
+
+    let latest = self.latest_device.clone();
+    let src = match self.copies.remove(&latest) {
+      Some(mem) => mem,
+      None => return Box::new(Err(Error::InvalidDevice).into_future())
+    };
+    let dst = match self.copies.remove(&bdev) {
+      Some(mem) => Ok(mem),
+      None => RawBuffer::<T>::alloc_on_device(&bdev, self.size * mem::size_of::<T>())
+    };
+    let dst = match dst {
+      Ok(mem) => mem,
+      Err(err) => {
+        self.copies.insert(latest, src);
+        return Box::new(Err(err).into_future())
+      }
+    };
+
+    match (src, dst) {
       #[cfg(feature = "native")]
-      BufferDevice::Native(_) => {
-        match bdev {
-          #[cfg(feature = "native")]
-          BufferDevice::Native(_) => Box::new(Ok(self).into_future()),
-        }
+      (BufferMemory::Native(src), BufferMemory::Native(mut dst)) => {
+        let pool = match bdev {
+          BufferDevice::Native(ref to) => to.pool().clone(),
+          #[cfg(feature = "cuda")]
+          BufferDevice::Cuda(ref to) => to.pool().clone()
+        };
+        Box::new(pool.spawn_fn(move || {
+          {
+            let bytes: &[u8] = try!(src.try_as_slice());
+            try!(dst.copy_from(bytes));
+          }
+          Ok((src, dst))
+        }).map(move |(src, dst)| {
+          self.copies.insert(latest, BufferMemory::Native(src));
+          self.copies.insert(bdev.clone(), BufferMemory::Native(dst));
+          self.latest_device = bdev;
+          self
+        }).map_err(Error::Native))
+      },
+      // Transfers involving a GPU are staged through host memory
+      #[cfg(feature = "cuda")]
+      (src, mut dst) => {
+        let pool = bdev.pool().clone();
+        Box::new(pool.spawn_fn(move || {
+          try!(dst.copy_from_bytes(&try!(src.to_bytes())));
+          Ok((src, dst))
+        }).map(move |(src, dst)| {
+          self.copies.insert(latest, src);
+          self.copies.insert(bdev.clone(), dst);
+          self.latest_device = bdev;
+          self
+        }))
       },
     }
   }
+
+}
+
+#[cfg(feature = "native")]
+impl<T: Send + Copy + Sized + 'static> LockedBuffer<T> {
+  pub fn slice(&self, range: Range<usize>) -> Result<BufferView<T>, Error> {
+    try!(self.check_range(&range));
+    let dev = match self.latest_device {
+      BufferDevice::Native(ref dev) => dev.clone(),
+      #[cfg(feature = "cuda")]
+      _ => return Err(Error::InvalidDevice)
+    };
+
+    Ok(BufferView {
+      mem: try!(self.native_memory(&dev)),
+      range: range,
+      _pd: PhantomData
+    })
+  }
+
+  pub fn slice_mut(&mut self, range: Range<usize>) -> Result<BufferViewMut<T>, Error> {
+    try!(self.check_range(&range));
+    let dev = match self.latest_device {
+      BufferDevice::Native(ref dev) => dev.clone(),
+      #[cfg(feature = "cuda")]
+      _ => return Err(Error::InvalidDevice)
+    };
+
+    Ok(BufferViewMut {
+      mem: try!(self.raw.native_memory_mut(&dev)),
+      range: range,
+      _pd: PhantomData
+    })
+  }
+
+  /// Overwrite this buffer with the contents of `src` on `dev`, without
+  /// staging them through a host vector. Both buffers must hold the same
+  /// number of elements, and are handed back once the copy is done.
+  pub fn copy_from(mut self, src: LockedBuffer<T>, dev: &native::Device) ->
+    Box<Future<Item=(LockedBuffer<T>, LockedBuffer<T>),Error=Error>> {
+    if self.size != src.size {
+      return Box::new(Err(Error::SizeMismatch { expected: self.size, actual: src.size }).into_future())
+    }
+
+    // The copy goes to new memory, so that our old contents are still
+    // there if it fails
+    let bdev = BufferDevice::Native(dev.clone());
+    let mut dst = match dev.alloc_memory(self.size * mem::size_of::<T>()) {
+      Ok(mem) => mem.tagged::<T>(),
+      Err(err) => return Box::new(Err(Error::Native(err)).into_future())
+    };
+
+    let dev = dev.clone();
+    let pool = dev.pool().clone();
+    Box::new(src.sync(&dev).and_then(move |src| {
+      pool.spawn_fn(move || {
+        {
+          let bytes: &[u8] = try!(try!(src.native_memory(&dev)).try_as_slice());
+          try!(dst.copy_from(bytes));
+        }
+        Ok((dst, src))
+      })
+    }).map(move |(dst, src)| {
+      self.raw.invalidate_copies(&bdev);
+      self.copies.insert(bdev, BufferMemory::Native(dst));
+      (self, src)
+    }))
+  }
+
+  /// Bring the copies on `devs` up to date with the latest one, which
+  /// stays the latest.
+  pub fn flush(self, devs: &[&native::Device]) -> Box<Future<Item=LockedBuffer<T>,Error=Error>> {
+    let latest = self.latest_device.clone();
+    let mut f: Box<Future<Item=LockedBuffer<T>,Error=Error>> = Box::new(Ok(self).into_future());
+    for dev in devs {
+      let bdev = BufferDevice::Native((*dev).clone());
+      f = Box::new(f.and_then(move |b| -> Box<Future<Item=LockedBuffer<T>,Error=Error>> {
+        // Writes drop every other copy, so any copy left is current
+        if b.copies.contains_key(&bdev) {
+          Box::new(Ok(b).into_future())
+        } else {
+          b.sync(bdev)
+        }
+      }));
+    }
+
+    Box::new(f.map(move |mut b| {
+      b.latest_device = latest;
+      b
+    }))
+  }
+
+  /// Replace every element `v` with `f(v)` on the workers of the
+  /// latest device, which must be native.
+  pub fn map_inplace<F: Fn(T) -> T + Send + 'static>(mut self, f: F) -> Box<Future<Item=LockedBuffer<T>,Error=Error>> {
+    let dev = match self.latest_device {
+      BufferDevice::Native(ref dev) => dev.clone(),
+      #[cfg(feature = "cuda")]
+      _ => return Box::new(Err(Error::InvalidDevice).into_future())
+    };
+
+    let bdev = BufferDevice::Native(dev.clone());
+    self.raw.invalidate_copies(&bdev);
+    let mut mem = match self.copies.remove(&bdev) {
+      Some(BufferMemory::Native(mem)) => mem,
+      _ => return Box::new(Err(Error::InvalidDevice).into_future())
+    };
+
+    Box::new(dev.pool().spawn_fn(move || {
+      for v in try!(mem.try_as_mut_slice::<T>()).iter_mut() {
+        *v = f(*v);
+      }
+      Ok(mem)
+    }).map(move |mem| {
+      self.raw.copies.insert(bdev, BufferMemory::Native(mem));
+      self
+    }).map_err(Error::Native))
+  }
+
+  /// Whether both buffers hold the same elements, compared on the
+  /// workers of `dev` after syncing both there. The locks are handed
+  /// back along with the result.
+  pub fn content_eq(self, other: LockedBuffer<T>, dev: &native::Device) ->
+    Box<Future<Item=(LockedBuffer<T>, LockedBuffer<T>, bool),Error=Error>>
+    where T: PartialEq {
+    if self.size != other.size {
+      return Box::new(Ok((self, other, false)).into_future())
+    }
+
+    let dev = dev.clone();
+    let pool = dev.pool().clone();
+    Box::new(self.sync(&dev).join(other.sync(&dev)).and_then(move |(a, b)| {
+      pool.spawn_fn(move || {
+        let eq = {
+          let x: &[T] = try!(try!(a.native_memory(&dev)).try_as_slice());
+          let y: &[T] = try!(try!(b.native_memory(&dev)).try_as_slice());
+          x == y
+        };
+        Ok((a, b, eq))
+      })
+    }))
+  }
+
+  /// Split into independent buffers of `chunk_size` elements on the
+  /// workers of the latest device, which must be native. The last one
+  /// holds whatever remains. The source is handed back with the chunks.
+  pub fn chunks(mut self, chunk_size: usize) -> Box<Future<Item=(LockedBuffer<T>, Vec<Buffer<T>>),Error=Error>> {
+    if chunk_size == 0 {
+      return Box::new(Err(Error::InvalidStep).into_future())
+    }
+
+    let dev = match self.latest_device {
+      BufferDevice::Native(ref dev) => dev.clone(),
+      #[cfg(feature = "cuda")]
+      _ => return Box::new(Err(Error::InvalidDevice).into_future())
+    };
+
+    let bdev = BufferDevice::Native(dev.clone());
+    let mem = match self.copies.remove(&bdev) {
+      Some(BufferMemory::Native(mem)) => mem,
+      _ => return Box::new(Err(Error::InvalidDevice).into_future())
+    };
+
+    Box::new(dev.pool().clone().spawn_fn(move || {
+      let chunks = {
+        let s: &[T] = try!(mem.try_as_slice());
+        try!(s.chunks(chunk_size).map(|c| Buffer::from_vec_native(&dev, c.to_vec())).collect::<Result<Vec<_>, _>>())
+      };
+      Ok((mem, chunks))
+    }).map(move |(mem, chunks)| {
+      self.raw.copies.insert(bdev, BufferMemory::Native(mem));
+      (self, chunks)
+    }))
+  }
+
+  fn check_range(&self, range: &Range<usize>) -> Result<(), Error> {
+    if range.start > range.end || range.end > self.size() {
+      Err(Error::OutOfBounds)
+    } else {
+      Ok(())
+    }
+  }
+}
+
+/// Half precision storage, converted to and from `f32` for compute.
+#[cfg(all(feature = "native", feature = "half"))]
+impl LockedBuffer<f16> {
+  /// Widen into a new `f32` buffer on `dev`, on its workers.
+  pub fn to_f32_buffer(self, dev: &native::Device) -> Box<Future<Item=Buffer<f32>,Error=Error>> {
+    convert(self, dev, f16::to_f32)
+  }
+
+  /// Narrow `src` into a new half precision buffer on `dev`, on its
+  /// workers. Values are rounded to the nearest `f16`.
+  pub fn from_f32(src: LockedBuffer<f32>, dev: &native::Device) -> Box<Future<Item=Buffer<f16>,Error=Error>> {
+    convert(src, dev, f16::from_f32)
+  }
+}
+
+// Copy `src` into a new buffer on `dev`, converting every element
+#[cfg(all(feature = "native", feature = "half"))]
+fn convert<T: Send + Copy + Sized + 'static,
+           U: Send + Copy + Sized + 'static>(src: LockedBuffer<T>,
+                                             dev: &native::Device,
+                                             f: fn(T) -> U) -> Box<Future<Item=Buffer<U>,Error=Error>> {
+  let dev = dev.clone();
+  let pool = dev.pool().clone();
+  Box::new(src.sync(&dev).and_then(move |src| {
+    pool.spawn_fn(move || {
+      let n_src: &[T] = try!(try!(src.native_memory(&dev)).try_as_slice());
+      let mut dst = try!(try!(Buffer::with_capacity_native(&dev, n_src.len())).try_lock());
+
+      {
+        let n_dst: &mut [U] = try!(try!(dst.native_memory_mut(&dev)).try_as_mut_slice());
+        for (d, s) in n_dst.iter_mut().zip(n_src.iter()) {
+          *d = f(*s);
+        }
+      }
+
+      Ok(dst.unlock())
+    })
+  }))
+}
+
+#[cfg(feature = "native")]
+impl<'a, T: Copy + 'static> BufferView<'a, T> {
+  pub fn len(&self) -> usize { self.range.len() }
+
+  pub fn is_empty(&self) -> bool { self.range.len() == 0 }
+
+  pub fn try_as_slice(&self) -> Result<&[T], Error> {
+    let s: &[T] = try!(self.mem.try_as_slice());
+    Ok(&s[self.range.clone()])
+  }
+}
+
+#[cfg(feature = "native")]
+impl<'a, T: Copy + 'static> BufferViewMut<'a, T> {
+  pub fn len(&self) -> usize { self.range.len() }
+
+  pub fn is_empty(&self) -> bool { self.range.len() == 0 }
+
+  pub fn try_as_slice(&self) -> Result<&[T], Error> {
+    let s: &[T] = try!(self.mem.try_as_slice());
+    Ok(&s[self.range.clone()])
+  }
+
+  pub fn try_as_mut_slice(&mut self) -> Result<&mut [T], Error> {
+    let s: &mut [T] = try!(self.mem.try_as_mut_slice());
+    Ok(&mut s[self.range.clone()])
+  }
 }
 
 impl<T> From<RawBuffer<T>> for Buffer<T> {
@@ -243,6 +1279,38 @@ impl<T: Send + Copy + Sized + 'static> Buffer<T> {
     Ok(raw.into())
   }
 
+  pub fn new_aligned<D: Into<BufferDevice>>(dev: D, size: usize, align: usize) -> Result<Buffer<T>, Error> {
+    let raw = try!(RawBuffer::new_aligned(dev, size, align));
+    Ok(raw.into())
+  }
+
+  /// Allocate page-locked host memory, to stage transfers to and from
+  /// other devices.
+  #[cfg(feature = "native")]
+  pub fn new_pinned(dev: &native::Device, size: usize) -> Result<Buffer<T>, Error> {
+    let raw = try!(RawBuffer::new_pinned(dev, size));
+    Ok(raw.into())
+  }
+
+  /// Allocate on the device's own workers instead of the calling
+  /// thread, resolving once the memory is ready.
+  pub fn new_async<D: Into<BufferDevice>>(dev: D, size: usize) -> Box<Future<Item=Buffer<T>,Error=Error>> {
+    let bdev = dev.into();
+    let pool = bdev.pool().clone();
+    Box::new(pool.spawn_fn(move || RawBuffer::new(bdev, size).map(Buffer::from)))
+  }
+
+  pub fn zeros<D: Into<BufferDevice>>(dev: D, size: usize) -> Result<Buffer<T>, Error> {
+    let mut raw = try!(RawBuffer::new(dev, size));
+    try!(raw.zero());
+    Ok(raw.into())
+  }
+
+  #[cfg(feature = "native")]
+  pub fn zeros_native(dev: &native::Device, size: usize) -> Result<Buffer<T>, Error> {
+    Self::zeros(dev, size)
+  }
+
   #[cfg(feature = "native")]
   pub fn with_capacity_native(dev: &native::Device, capacity: usize) -> Result<Buffer<T>, Error> {
     Self::new(dev, capacity)
@@ -254,13 +1322,265 @@ impl<T: Send + Copy + Sized + 'static> Buffer<T> {
     Ok(raw.into())
   }
 
+  /// Allocate and fill with `value` on the device's workers.
+  #[cfg(feature = "native")]
+  pub fn filled_native(dev: &native::Device, size: usize, value: T) -> Box<Future<Item=Buffer<T>,Error=Error>> {
+    let buf: Buffer<T> = match Self::new(dev, size) {
+      Ok(buf) => buf,
+      Err(e) => return Box::new(Err(e).into_future())
+    };
+
+    Box::new(buf.lock().and_then(move |b| b.fill(value)).map(|b| b.unlock()))
+  }
+
+  #[cfg(feature = "native")]
+  pub fn full_native(dev: &native::Device, size: usize, value: T) -> Box<Future<Item=Buffer<T>,Error=Error>> {
+    Self::filled_native(dev, size, value)
+  }
+
+  #[cfg(feature = "native")]
+  pub fn ones_native(dev: &native::Device, size: usize) -> Box<Future<Item=Buffer<T>,Error=Error>>
+    where T: One {
+    Self::full_native(dev, size, T::one())
+  }
+
+  /// Values from `start` up to but excluding `end`, `step` apart,
+  /// generated on the device's workers.
+  #[cfg(feature = "native")]
+  pub fn arange_native(dev: &native::Device, start: T, end: T, step: T) -> Box<Future<Item=Buffer<T>,Error=Error>>
+    where T: Numeric {
+    if step == T::zero() {
+      return Box::new(Err(Error::InvalidStep).into_future())
+    }
+
+    let dev = dev.clone();
+    Box::new(dev.pool().clone().spawn_fn(move || {
+      let span = (end.to_f64() - start.to_f64()) / step.to_f64();
+      let n = if span > 0.0 { span.ceil() as usize } else { 0 };
+      let vs = (0..n).map(|i| start + step * T::from_usize(i)).collect();
+      Self::from_vec_native(&dev, vs)
+    }))
+  }
+
+  /// `n` evenly spaced values from `start` to `end` inclusive,
+  /// generated on the device's workers.
+  #[cfg(feature = "native")]
+  pub fn linspace_native(dev: &native::Device, start: T, end: T, n: usize) -> Box<Future<Item=Buffer<T>,Error=Error>>
+    where T: Numeric {
+    let dev = dev.clone();
+    Box::new(dev.pool().clone().spawn_fn(move || {
+      let vs = match n {
+        0 => Vec::new(),
+        1 => vec![start],
+        _ => {
+          // Offsets are worked out in f64, as `(end - start) * i` easily
+          // overflows integer types
+          let span = end.to_f64() - start.to_f64();
+          let last = (n - 1) as f64;
+          let mut vs: Vec<T> = (0..n).map(|i| start + T::from_f64(span * i as f64 / last)).collect();
+          vs[n - 1] = end;
+          vs
+        }
+      };
+      Self::from_vec_native(&dev, vs)
+    }))
+  }
+
+  /// Values drawn uniformly from `[low, high)` on the device's workers.
+  /// The same `seed` always produces the same values.
+  #[cfg(feature = "native")]
+  pub fn random_uniform_native(dev: &native::Device,
+                               size: usize,
+                               low: T,
+                               high: T,
+                               seed: u64) -> Box<Future<Item=Buffer<T>,Error=Error>>
+    where T: Numeric {
+    let dev = dev.clone();
+    Box::new(dev.pool().clone().spawn_fn(move || {
+      let mut rng = SmallRng::seed_from_u64(seed);
+      let (low, high) = (low.to_f64(), high.to_f64());
+      let vs = (0..size).map(|_| T::from_f64(low + (high - low) * rng.gen::<f64>())).collect();
+      Self::from_vec_native(&dev, vs)
+    }))
+  }
+
+  /// Values drawn from a normal distribution on the device's workers.
+  /// The same `seed` always produces the same values.
+  #[cfg(feature = "native")]
+  pub fn random_normal_native(dev: &native::Device,
+                              size: usize,
+                              mean: T,
+                              std: T,
+                              seed: u64) -> Box<Future<Item=Buffer<T>,Error=Error>>
+    where T: Numeric {
+    let dev = dev.clone();
+    Box::new(dev.pool().clone().spawn_fn(move || {
+      let mut rng = SmallRng::seed_from_u64(seed);
+      let (mean, std) = (mean.to_f64(), std.to_f64());
+
+      // Box-Muller, each pair of uniform samples yields two normal ones
+      let mut vs = Vec::with_capacity(size);
+      while vs.len() < size {
+        let u1 = 1.0 - rng.gen::<f64>();
+        let u2 = rng.gen::<f64>();
+        let r = (-2.0 * u1.ln()).sqrt();
+        let theta = 2.0 * f64::consts::PI * u2;
+
+        vs.push(T::from_f64(mean + std * r * theta.cos()));
+        if vs.len() < size {
+          vs.push(T::from_f64(mean + std * r * theta.sin()));
+        }
+      }
+      Self::from_vec_native(&dev, vs)
+    }))
+  }
+
+  /// Join the contents of `bufs` end to end into a new buffer on `dev`,
+  /// on its workers. The latest copy of each input must be on a native
+  /// device, the locks are handed back along with the result.
+  #[cfg(feature = "native")]
+  pub fn concat_native(dev: &native::Device, mut bufs: Vec<LockedBuffer<T>>) ->
+    Box<Future<Item=(Vec<LockedBuffer<T>>, Buffer<T>),Error=Error>> {
+    let native = bufs.iter().all(|b| match (&b.latest_device, b.copies.get(&b.latest_device)) {
+      (&BufferDevice::Native(_), Some(&BufferMemory::Native(_))) => true,
+      _ => false
+    });
+    if !native {
+      return Box::new(Err(Error::InvalidDevice).into_future())
+    }
+
+    let size = bufs.iter().map(|b| b.size()).sum();
+    let mut raw = match RawBuffer::new(dev, size) {
+      Ok(raw) => raw,
+      Err(e) => return Box::new(Err(e).into_future())
+    };
+
+    let bdev = BufferDevice::Native(dev.clone());
+    let mut dst = match raw.copies.remove(&bdev) {
+      Some(BufferMemory::Native(mem)) => mem,
+      _ => return Box::new(Err(Error::InvalidDevice).into_future())
+    };
+    let srcs: Vec<native::Memory> = bufs.iter_mut().filter_map(|b| {
+      let latest = b.latest_device.clone();
+      match b.copies.remove(&latest) {
+        Some(BufferMemory::Native(mem)) => Some(mem),
+        _ => None
+      }
+    }).collect();
+
+    Box::new(dev.pool().spawn_fn(move || {
+      {
+        let out: &mut [T] = try!(dst.try_as_mut_slice());
+        let mut offset = 0;
+        for src in srcs.iter() {
+          let s: &[T] = try!(src.try_as_slice());
+          out[offset..(offset + s.len())].copy_from_slice(s);
+          offset += s.len();
+        }
+      }
+      Ok((srcs, dst))
+    }).map(move |(srcs, dst)| {
+      for (b, src) in bufs.iter_mut().zip(srcs) {
+        let latest = b.latest_device.clone();
+        b.raw.copies.insert(latest, BufferMemory::Native(src));
+      }
+      raw.copies.insert(bdev, BufferMemory::Native(dst));
+      (bufs, raw.into())
+    }).map_err(Error::Native))
+  }
+
+  /// Copy `vs` onto `dev` as plain bytes.
+  #[cfg(all(feature = "native", feature = "bytemuck"))]
+  pub fn from_pod_slice(dev: &native::Device, vs: &[T]) -> Result<Buffer<T>, Error>
+    where T: bytemuck::Pod {
+    let bytes: &[u8] = bytemuck::cast_slice(vs);
+    let mut mem = try!(dev.alloc_memory(bytes.len())).tagged::<T>();
+    try!(mem.copy_from(bytes));
+
+    let raw = try!(RawBuffer::from_memory_native(dev, vs.len(), mem));
+    Ok(raw.into())
+  }
+
+  /// Copy an array onto `dev` in standard order, along with its shape.
+  #[cfg(all(feature = "native", feature = "ndarray"))]
+  pub fn from_ndarray<D: Dimension>(dev: &native::Device,
+                                    array: &ArrayView<T, D>) -> Result<(Buffer<T>, Vec<usize>), Error> {
+    let vec = match array.as_slice() {
+      Some(s) => s.to_vec(),
+      None => array.iter().cloned().collect()
+    };
+
+    let buffer = try!(Self::from_vec_native(dev, vec));
+    Ok((buffer, array.shape().to_vec()))
+  }
+
+  #[cfg(all(feature = "native", feature = "serialize"))]
+  pub fn from_serialized(dev: &native::Device, snapshot: BufferSnapshot<T>) -> Result<Buffer<T>, Error> {
+    if snapshot.data.len() != snapshot.size {
+      return Err(Error::InvalidRawBuffer)
+    }
+
+    Self::from_vec_native(dev, snapshot.data)
+  }
+
   pub fn lock(&self) -> Box<Future<Item=LockedBuffer<T>,Error=Error>> {
     Box::new(self.raw.lock().map(|raw| LockedBuffer {
       raw: raw
     }).map_err(|_| Error::InvalidLock))
   }
 
+  pub fn id(&self) -> BufferId {
+    BufferId(self.raw.id())
+  }
+
+  /// Whether both handles refer to the same buffer.
+  pub fn ptr_eq(this: &Buffer<T>, other: &Buffer<T>) -> bool {
+    Vault::ptr_eq(&this.raw, &other.raw)
+  }
+
   pub fn try_lock(&self) -> Result<LockedBuffer<T>,Error> {
     self.raw.try_lock().map(|raw| raw.into()).map_err(|_| Error::InvalidLock)
   }
+
+  /// Take back the `RawBuffer` if this is the only handle to it,
+  /// otherwise the `Buffer` is returned unchanged.
+  pub fn into_raw(self) -> Result<RawBuffer<T>, Buffer<T>> {
+    self.raw.into_inner().map_err(|raw| Buffer { raw: raw })
+  }
+}
+
+/// Wait for all of `futs`, resolving to their buffers in the same
+/// order. Fails with the first error, the remaining futures are then
+/// dropped.
+pub fn join_buffers<T: 'static>(futs: Vec<Box<Future<Item=LockedBuffer<T>,Error=Error>>>)
+                                -> Box<Future<Item=Vec<LockedBuffer<T>>,Error=Error>> {
+  Box::new(future::join_all(futs))
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_failed_copy_from_keeps_contents() {
+    use futures::Future;
+    use backend::Backend;
+
+    let backend = native::Backend::default();
+    let dev = backend.device().clone();
+    let dst = Buffer::from_vec_native(&dev, vec![1.0f32, 2.0, 3.0]).unwrap();
+
+    // A source whose latest copy is missing, so syncing it fails
+    let src: Buffer<f32> = RawBuffer {
+      size: 3,
+      copies: HashMap::new(),
+      latest_device: (&dev).into(),
+      _pd: PhantomData
+    }.into();
+
+    let r = dst.lock().join(src.lock()).and_then(|(d, s)| d.copy_from(s, &dev)).wait();
+    assert!(match r { Err(Error::InvalidDevice) => true, _ => false });
+    assert_eq!(dst.lock().and_then(|d| d.sync_to_vec()).wait().unwrap(), vec![1.0, 2.0, 3.0]);
+  }
 }
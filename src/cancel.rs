@@ -0,0 +1,68 @@
+//! Cooperative cancellation of queued and running operations.
+//!
+//! A `CancelToken` is shared between whoever may cancel and the
+//! operations being cancelled. Futures guarded by a token resolve to
+//! `Error::Cancelled` the next time they are polled after it fires, and
+//! kernels running on a pool check it between chunks of work so that
+//! the rest is skipped.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use futures::{Future, Poll};
+use buffer::Error;
+
+/// Clones share the same flag, cancelling one cancels them all.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+  cancelled: Arc<AtomicBool>
+}
+
+/// `Cancellable` is returned by `CancelToken::guard`, resolving like
+/// the future it wraps until the token is cancelled.
+pub struct Cancellable<F> {
+  token: CancelToken,
+  inner: F
+}
+
+impl CancelToken {
+  pub fn new() -> CancelToken {
+    CancelToken::default()
+  }
+
+  pub fn cancel(&self) {
+    self.cancelled.store(true, Ordering::SeqCst);
+  }
+
+  pub fn is_cancelled(&self) -> bool {
+    self.cancelled.load(Ordering::SeqCst)
+  }
+
+  /// Fails with `Error::Cancelled` once cancelled, for kernels to call
+  /// between chunks of work.
+  pub fn check(&self) -> Result<(), Error> {
+    if self.is_cancelled() {
+      Err(Error::Cancelled)
+    } else {
+      Ok(())
+    }
+  }
+
+  /// Stop polling `f` once cancelled and resolve to `Error::Cancelled`
+  /// instead.
+  pub fn guard<F: Future<Error=Error>>(&self, f: F) -> Cancellable<F> {
+    Cancellable {
+      token: self.clone(),
+      inner: f
+    }
+  }
+}
+
+impl<F: Future<Error=Error>> Future for Cancellable<F> {
+  type Item = F::Item;
+  type Error = Error;
+
+  fn poll(&mut self) -> Poll<F::Item, Error> {
+    try!(self.token.check());
+    self.inner.poll()
+  }
+}
@@ -5,6 +5,15 @@ use futures::Future;
 use hardware::Hardware;
 use memory::Memory;
 
+/// Limits of a device, as far as they can be determined. Sizes are in
+/// bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceCapabilities {
+  pub total_memory: usize,
+  pub max_single_allocation: usize,
+  pub logical_cores: usize
+}
+
 pub trait Device {
   type H: Hardware;
   type M: Memory;
@@ -12,11 +21,21 @@ pub trait Device {
 
   fn id(&self) -> isize;
   fn hardware(&self) -> &Self::H;
+  fn capabilities(&self) -> DeviceCapabilities;
   fn alloc_memory(&self, size: usize) -> Result<Self::M, Self::Error>;
   fn sync_from_vec<T: Send + Copy + Sized + 'static>(&self,
                                                      mem: Self::M,
                                                      vec: Vec<T>) -> Box<Future<Item=Self::M,Error=Self::Error>>;
 
+  fn sync_from_vec_at<T: Send + Copy + Sized + 'static>(&self,
+                                                        mem: Self::M,
+                                                        offset: usize,
+                                                        vec: Vec<T>) -> Box<Future<Item=Self::M,Error=Self::Error>>;
+
   fn sync_to_vec<T: Send + Copy + Sized + 'static>(&self,
                                                    mem: Self::M) -> Box<Future<Item=(Self::M, Vec<T>),Error=Self::Error>>;
+
+  fn fill<T: Send + Copy + Sized + 'static>(&self,
+                                            mem: Self::M,
+                                            value: T) -> Box<Future<Item=Self::M,Error=Self::Error>>;
 }
@@ -2,6 +2,24 @@ use hardware::Hardware;
 use device::Device;
 use std::fmt;
 
+/// Kinds of operation a framework may be able to run, so that work
+/// can be routed to a framework supporting it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OpKind {
+  Dot,
+  Gemm,
+  Gemv,
+  Axpy,
+  Scal,
+  Norm,
+  Reduce,
+  ArgReduce,
+  Activation,
+  Softmax,
+  Scalar,
+  Clamp
+}
+
 pub trait Framework {
   type H: Hardware;
   type D: Device + Clone;
@@ -11,4 +29,7 @@ pub trait Framework {
   fn new() -> Self where Self: Sized;
   fn load_hardwares(&self) -> Result<Vec<Self::H>, Self::Error>;
   fn new_device(&self, &Self::H) -> Result<Self::D, Self::Error>;
+
+  /// Every kind of operation this framework can run.
+  fn supported_ops(&self) -> &[OpKind];
 }
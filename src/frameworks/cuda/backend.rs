@@ -0,0 +1,34 @@
+use framework::Framework as IFramework;
+use backend;
+use buffer::BufferDevice;
+
+use super::Framework;
+use super::Device;
+use super::Error;
+
+#[derive(Clone)]
+pub struct Backend {
+  device: Device
+}
+
+impl Backend {
+  /// A backend on the first CUDA device, failing when there is none.
+  pub fn new() -> Result<Backend, Error> {
+    let framework = Framework::new();
+    let hardwares = try!(framework.load_hardwares());
+    let hardware = try!(hardwares.into_iter().next().ok_or(Error::NoDevice));
+
+    Ok(Backend {
+      device: try!(framework.new_device(&hardware))
+    })
+  }
+
+  /// Every device this backend can run on.
+  pub fn devices(&self) -> Vec<BufferDevice> {
+    vec![self.device.clone().into()]
+  }
+}
+
+impl backend::Backend<Framework> for Backend {
+  fn device(&self) -> &Device { &self.device }
+}
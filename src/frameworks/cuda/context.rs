@@ -0,0 +1,33 @@
+use std::ptr;
+
+use super::sys;
+use super::error::{check, Error};
+
+/// A CUDA context, destroyed once the device and all of its memory are
+/// dropped. Driver calls are only valid while a context is current on
+/// the calling thread, so everything touching the GPU makes it current
+/// first.
+pub struct Context {
+  raw: sys::CUcontext
+}
+
+unsafe impl Send for Context { }
+unsafe impl Sync for Context { }
+
+impl Context {
+  pub fn new(dev: sys::CUdevice) -> Result<Context, Error> {
+    let mut raw = ptr::null_mut();
+    try!(check(unsafe { sys::cuCtxCreate(&mut raw, 0, dev) }));
+    Ok(Context { raw: raw })
+  }
+
+  pub fn make_current(&self) -> Result<(), Error> {
+    check(unsafe { sys::cuCtxSetCurrent(self.raw) })
+  }
+}
+
+impl Drop for Context {
+  fn drop(&mut self) {
+    unsafe { sys::cuCtxDestroy(self.raw); }
+  }
+}
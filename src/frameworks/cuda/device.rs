@@ -0,0 +1,134 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicIsize, Ordering};
+use std::hash::{Hash, Hasher};
+use std::fmt;
+
+use futures::Future;
+use futures_cpupool::{CpuPool, Builder};
+
+use device;
+use hardware::Hardware as IHardware;
+use super::sys;
+use super::Hardware;
+use super::Memory;
+use super::Error;
+use super::context::Context;
+use super::error::check;
+
+static NEXT_ID: AtomicIsize = AtomicIsize::new(0);
+
+#[derive(Debug, Clone)]
+pub struct Device {
+  id: isize,
+  inner: Arc<Inner>
+}
+
+struct Inner {
+  hardware: Hardware,
+  capabilities: device::DeviceCapabilities,
+  context: Arc<Context>,
+
+  // A single thread issues the blocking driver calls for this device
+  pool: CpuPool
+}
+
+impl Device {
+  pub fn new(hardware: Hardware) -> Result<Device, Error> {
+    let context = try!(Context::new(hardware.ordinal()));
+    let mut total_memory = 0;
+    try!(check(unsafe { sys::cuDeviceTotalMem(&mut total_memory, hardware.ordinal()) }));
+    let capabilities = device::DeviceCapabilities {
+      total_memory: total_memory,
+      max_single_allocation: total_memory,
+      logical_cores: hardware.compute_units()
+    };
+
+    let mut builder = Builder::new();
+    builder.pool_size(1);
+
+    let inner = Arc::new(Inner {
+      hardware: hardware,
+      capabilities: capabilities,
+      context: Arc::new(context),
+      pool: builder.create()
+    });
+
+    Ok(Device {
+      id: NEXT_ID.fetch_add(1, Ordering::SeqCst),
+      inner: inner
+    })
+  }
+
+  pub fn pool(&self) -> &CpuPool {
+    &self.inner.pool
+  }
+}
+
+impl device::Device for Device {
+  type H = Hardware;
+  type M = Memory;
+  type Error = Error;
+
+  fn id(&self) -> isize { self.id }
+  fn hardware(&self) -> &Self::H { &self.inner.hardware }
+  fn capabilities(&self) -> device::DeviceCapabilities { self.inner.capabilities }
+  fn alloc_memory(&self, size: usize) -> Result<Self::M, Self::Error> {
+    Memory::alloc(self.inner.context.clone(), size)
+  }
+
+  fn sync_from_vec<T: Send + Copy + Sized + 'static>(&self,
+                                                     mut mem: Self::M,
+                                                     vec: Vec<T>) -> Box<Future<Item=Self::M,Error=Self::Error>> {
+    Box::new(self.inner.pool.spawn_fn(move || {
+      try!(mem.copy_from(&vec));
+      Ok(mem)
+    }))
+  }
+
+  fn sync_from_vec_at<T: Send + Copy + Sized + 'static>(&self,
+                                                        mut mem: Self::M,
+                                                        offset: usize,
+                                                        vec: Vec<T>) -> Box<Future<Item=Self::M,Error=Self::Error>> {
+    Box::new(self.inner.pool.spawn_fn(move || {
+      try!(mem.copy_from_at(offset, &vec));
+      Ok(mem)
+    }))
+  }
+
+  fn sync_to_vec<T: Send + Copy + Sized + 'static>(&self,
+                                                   mem: Self::M) -> Box<Future<Item=(Self::M, Vec<T>),Error=Self::Error>> {
+    Box::new(self.inner.pool.spawn_fn(move || {
+      let vec: Vec<T> = try!(mem.to_vec());
+      Ok((mem, vec))
+    }))
+  }
+
+  fn fill<T: Send + Copy + Sized + 'static>(&self,
+                                            mut mem: Self::M,
+                                            value: T) -> Box<Future<Item=Self::M,Error=Self::Error>> {
+    Box::new(self.inner.pool.spawn_fn(move || {
+      try!(mem.fill(value));
+      Ok(mem)
+    }))
+  }
+}
+
+impl PartialEq for Device {
+  fn eq(&self, o: &Self) -> bool {
+    self.id == o.id
+  }
+}
+
+impl Eq for Device { }
+
+impl Hash for Device {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.id.hash(state);
+  }
+}
+
+impl fmt::Debug for Inner {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "Inner {{ hardware: {:?} }}", &self.hardware)
+  }
+}
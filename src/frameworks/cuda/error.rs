@@ -0,0 +1,33 @@
+use std::error;
+use std::fmt;
+
+use super::sys;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Error {
+  OutOfMemory,
+  InvalidSize,
+  NoDevice,
+  Driver(sys::CUresult)
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Error::OutOfMemory => write!(f, "out of device memory"),
+      Error::InvalidSize => write!(f, "copy does not fit the device memory"),
+      Error::NoDevice => write!(f, "no CUDA device found"),
+      Error::Driver(code) => write!(f, "CUDA driver error {}", code)
+    }
+  }
+}
+
+impl error::Error for Error { }
+
+pub fn check(result: sys::CUresult) -> Result<(), Error> {
+  match result {
+    sys::CUDA_SUCCESS => Ok(()),
+    sys::CUDA_ERROR_OUT_OF_MEMORY => Err(Error::OutOfMemory),
+    err => Err(Error::Driver(err))
+  }
+}
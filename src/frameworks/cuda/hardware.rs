@@ -0,0 +1,27 @@
+use hardware;
+use super::sys;
+
+#[derive(Debug, Clone)]
+pub struct Hardware {
+  ordinal: i32,
+  name: String,
+  compute_units: usize
+}
+
+impl Hardware {
+  pub fn new(ordinal: i32, name: String, compute_units: usize) -> Hardware {
+    Hardware {
+      ordinal: ordinal,
+      name: name,
+      compute_units: compute_units
+    }
+  }
+
+  pub fn ordinal(&self) -> sys::CUdevice { self.ordinal }
+}
+
+impl hardware::Hardware for Hardware {
+  fn name(&self) -> &str { &self.name }
+  fn hardware_type(&self) -> hardware::HardwareType { hardware::HardwareType::GPU }
+  fn compute_units(&self) -> usize { self.compute_units }
+}
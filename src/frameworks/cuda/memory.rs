@@ -0,0 +1,130 @@
+use std::mem;
+use std::fmt;
+use std::sync::Arc;
+use std::os::raw::c_void;
+
+use memory;
+use super::sys;
+use super::context::Context;
+use super::error::{check, Error};
+
+/// Memory on a CUDA device. It can only be reached through explicit
+/// copies to and from the host.
+pub struct Memory {
+  ptr: sys::CUdeviceptr,
+  len: usize,
+  context: Arc<Context>
+}
+
+impl Memory {
+  pub fn alloc(context: Arc<Context>, size: usize) -> Result<Memory, Error> {
+    let mut ptr = 0;
+    if size != 0 {
+      try!(context.make_current());
+      try!(check(unsafe { sys::cuMemAlloc(&mut ptr, size) }));
+    }
+
+    Ok(Memory {
+      ptr: ptr,
+      len: size,
+      context: context
+    })
+  }
+
+  pub fn len(&self) -> usize { self.len }
+
+  pub fn is_empty(&self) -> bool { self.len == 0 }
+
+  pub fn as_device_ptr(&self) -> sys::CUdeviceptr { self.ptr }
+
+  pub fn copy_from<T: Sized + Copy>(&mut self, vs: &[T]) -> Result<(), Error> {
+    if self.len != vs.len() * mem::size_of::<T>() {
+      return Err(Error::InvalidSize)
+    }
+
+    self.copy_from_at(0, vs)
+  }
+
+  pub fn copy_from_at<T: Sized + Copy>(&mut self, offset: usize, vs: &[T]) -> Result<(), Error> {
+    let size = mem::size_of::<T>();
+    let end = offset.checked_add(vs.len()).and_then(|n| n.checked_mul(size));
+    if end.map_or(true, |end| end > self.len) {
+      return Err(Error::InvalidSize)
+    }
+    if vs.is_empty() {
+      return Ok(())
+    }
+
+    try!(self.context.make_current());
+    check(unsafe {
+      sys::cuMemcpyHtoD(self.ptr + (offset * size) as u64,
+                        vs.as_ptr() as *const c_void,
+                        vs.len() * size)
+    })
+  }
+
+  pub fn copy_to<T: Sized + Copy>(&self, vs: &mut [T]) -> Result<(), Error> {
+    if vs.len() * mem::size_of::<T>() > self.len {
+      return Err(Error::InvalidSize)
+    }
+    if vs.is_empty() {
+      return Ok(())
+    }
+
+    try!(self.context.make_current());
+    check(unsafe {
+      sys::cuMemcpyDtoH(vs.as_mut_ptr() as *mut c_void, self.ptr, vs.len() * mem::size_of::<T>())
+    })
+  }
+
+  pub fn zero(&mut self) -> Result<(), Error> {
+    if self.len == 0 {
+      return Ok(())
+    }
+
+    try!(self.context.make_current());
+    check(unsafe { sys::cuMemsetD8(self.ptr, 0, self.len) })
+  }
+
+  pub fn fill<T: Sized + Copy>(&mut self, value: T) -> Result<(), Error> {
+    let vs = vec![value; self.len / mem::size_of::<T>()];
+    self.copy_from_at(0, &vs)
+  }
+
+  pub fn to_vec<T: Sized + Copy>(&self) -> Result<Vec<T>, Error> {
+    if self.len % mem::size_of::<T>() != 0 {
+      return Err(Error::InvalidSize)
+    }
+
+    // Copy into the spare capacity, the elements only exist once the
+    // copy has succeeded
+    let n = self.len / mem::size_of::<T>();
+    let mut vs: Vec<T> = Vec::with_capacity(n);
+    if n != 0 {
+      try!(self.context.make_current());
+      try!(check(unsafe {
+        sys::cuMemcpyDtoH(vs.as_mut_ptr() as *mut c_void, self.ptr, self.len)
+      }));
+    }
+    unsafe { vs.set_len(n); }
+    Ok(vs)
+  }
+}
+
+impl Drop for Memory {
+  fn drop(&mut self) {
+    if self.len != 0 && self.context.make_current().is_ok() {
+      unsafe { sys::cuMemFree(self.ptr); }
+    }
+  }
+}
+
+impl memory::Memory for Memory {
+  fn byte_len(&self) -> usize { self.len }
+}
+
+impl fmt::Debug for Memory {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "Memory {{ ptr: {:#x}, len: {} }}", self.ptr, self.len)
+  }
+}
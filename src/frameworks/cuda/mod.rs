@@ -0,0 +1,62 @@
+mod sys;
+mod context;
+mod device;
+mod error;
+mod hardware;
+mod memory;
+mod backend;
+
+use std::ffi::CStr;
+use std::os::raw::c_char;
+
+pub use self::device::Device;
+pub use self::hardware::Hardware;
+pub use self::memory::Memory;
+pub use self::error::Error;
+pub use self::backend::Backend;
+
+use self::error::check;
+use framework::{Framework as IFramework, OpKind};
+
+pub struct Framework { }
+
+impl IFramework for Framework {
+  type H = Hardware;
+  type D = Device;
+  type Error = error::Error;
+
+  fn name() -> &'static str { "cuda" }
+
+  fn new() -> Self where Self: Sized {
+    Framework { }
+  }
+
+  fn load_hardwares(&self) -> Result<Vec<Self::H>, Self::Error> {
+    let mut count = 0;
+    try!(check(unsafe { sys::cuInit(0) }));
+    try!(check(unsafe { sys::cuDeviceGetCount(&mut count) }));
+
+    (0..count).map(|ordinal| {
+      let mut dev = 0;
+      let mut name = [0 as c_char; 256];
+      let mut units = 0;
+      unsafe {
+        try!(check(sys::cuDeviceGet(&mut dev, ordinal)));
+        try!(check(sys::cuDeviceGetName(name.as_mut_ptr(), name.len() as i32, dev)));
+        try!(check(sys::cuDeviceGetAttribute(&mut units, sys::CU_DEVICE_ATTRIBUTE_MULTIPROCESSOR_COUNT, dev)));
+      }
+
+      let name = unsafe { CStr::from_ptr(name.as_ptr()) }.to_string_lossy().into_owned();
+      Ok(Hardware::new(dev, name, units as usize))
+    }).collect()
+  }
+
+  fn new_device(&self, hardware: &Self::H) -> Result<Self::D, Self::Error> {
+    Device::new(hardware.clone())
+  }
+
+  // Devices only hold memory so far, no kernels run on them
+  fn supported_ops(&self) -> &[OpKind] {
+    &[]
+  }
+}
@@ -0,0 +1,46 @@
+//! Just enough of the CUDA driver API to allocate memory and move it
+//! between the host and a device.
+
+#![allow(non_camel_case_types, non_snake_case)]
+
+use std::os::raw::{c_char, c_int, c_uint, c_void};
+
+pub type CUresult = c_int;
+pub type CUdevice = c_int;
+pub type CUdeviceptr = u64;
+
+pub enum CUctx_st { }
+pub type CUcontext = *mut CUctx_st;
+
+pub const CUDA_SUCCESS: CUresult = 0;
+pub const CUDA_ERROR_OUT_OF_MEMORY: CUresult = 2;
+pub const CU_DEVICE_ATTRIBUTE_MULTIPROCESSOR_COUNT: c_int = 16;
+
+#[link(name = "cuda")]
+extern "C" {
+  pub fn cuInit(flags: c_uint) -> CUresult;
+
+  pub fn cuDeviceGetCount(count: *mut c_int) -> CUresult;
+  pub fn cuDeviceGet(device: *mut CUdevice, ordinal: c_int) -> CUresult;
+  pub fn cuDeviceGetName(name: *mut c_char, len: c_int, dev: CUdevice) -> CUresult;
+  pub fn cuDeviceGetAttribute(pi: *mut c_int, attrib: c_int, dev: CUdevice) -> CUresult;
+  #[link_name = "cuDeviceTotalMem_v2"]
+  pub fn cuDeviceTotalMem(bytes: *mut usize, dev: CUdevice) -> CUresult;
+
+  #[link_name = "cuCtxCreate_v2"]
+  pub fn cuCtxCreate(pctx: *mut CUcontext, flags: c_uint, dev: CUdevice) -> CUresult;
+  #[link_name = "cuCtxDestroy_v2"]
+  pub fn cuCtxDestroy(ctx: CUcontext) -> CUresult;
+  pub fn cuCtxSetCurrent(ctx: CUcontext) -> CUresult;
+
+  #[link_name = "cuMemAlloc_v2"]
+  pub fn cuMemAlloc(dptr: *mut CUdeviceptr, bytesize: usize) -> CUresult;
+  #[link_name = "cuMemFree_v2"]
+  pub fn cuMemFree(dptr: CUdeviceptr) -> CUresult;
+  #[link_name = "cuMemcpyHtoD_v2"]
+  pub fn cuMemcpyHtoD(dst: CUdeviceptr, src: *const c_void, bytes: usize) -> CUresult;
+  #[link_name = "cuMemcpyDtoH_v2"]
+  pub fn cuMemcpyDtoH(dst: *mut c_void, src: CUdeviceptr, bytes: usize) -> CUresult;
+  #[link_name = "cuMemsetD8_v2"]
+  pub fn cuMemsetD8(dst: CUdeviceptr, value: u8, n: usize) -> CUresult;
+}
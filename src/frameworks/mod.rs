@@ -1 +1,38 @@
 pub mod native;
+#[cfg(feature = "cuda")]
+pub mod cuda;
+
+use framework::Framework;
+use buffer::BufferDevice;
+use spin;
+
+// Created by the first call to `enumerate`
+static DEVICES: spin::Once<Vec<BufferDevice>> = spin::Once::INIT;
+
+/// A device for every piece of hardware found on this machine, across
+/// all enabled frameworks. Frameworks that fail to load, such as CUDA
+/// on a machine with the driver library but no GPU, contribute no
+/// devices.
+///
+/// The devices, and their worker pools, are created once on the first
+/// call. Every later call hands out the same devices, so listing is
+/// cheap and devices from different calls compare equal.
+pub fn enumerate() -> Vec<BufferDevice> {
+  DEVICES.call_once(|| {
+    let mut devices = Vec::new();
+
+    #[cfg(feature = "native")]
+    devices.extend(load_devices(&native::Framework::new()).into_iter().map(BufferDevice::Native));
+
+    #[cfg(feature = "cuda")]
+    devices.extend(load_devices(&cuda::Framework::new()).into_iter().map(BufferDevice::Cuda));
+
+    devices
+  }).clone()
+}
+
+fn load_devices<F: Framework>(framework: &F) -> Vec<F::D> {
+  framework.load_hardwares().unwrap_or_default().iter().
+    filter_map(|h| framework.new_device(h).ok()).
+    collect()
+}
@@ -1,9 +1,13 @@
 use framework::Framework as IFramework;
 use backend;
+use buffer::BufferDevice;
 
 use super::Framework;
 use super::Device;
+use super::Error;
+use futures::Future;
 
+#[derive(Clone)]
 pub struct Backend {
   device: Device
 }
@@ -14,6 +18,33 @@ impl Backend {
       device: Framework::new().default_device()
     }
   }
+
+  pub fn with_threads(threads: usize) -> Result<Backend, Error> {
+    let framework = Framework::new();
+    let device = try!(framework.new_device_with_threads(&framework.default_hardware(), threads));
+    Ok(Backend {
+      device: device
+    })
+  }
+
+  /// Every device this backend can run on.
+  pub fn devices(&self) -> Vec<BufferDevice> {
+    vec![self.device.clone().into()]
+  }
+
+  /// Resolves once all work queued on the device before the call has
+  /// finished.
+  pub fn synchronize(&self) -> Box<Future<Item=(),Error=Error>> {
+    self.device.synchronize()
+  }
+}
+
+impl From<Device> for Backend {
+  fn from(device: Device) -> Backend {
+    Backend {
+      device: device
+    }
+  }
 }
 
 impl backend::Backend<Framework> for Backend {
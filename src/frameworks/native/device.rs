@@ -1,6 +1,7 @@
-use std::sync::Arc;
+use std::sync::{Arc, Barrier, Mutex};
+use std::sync::atomic::{AtomicIsize, AtomicUsize, Ordering};
 
-use futures::Future;
+use futures::{future, Future};
 use futures_cpupool::{CpuPool, Builder};
 
 use device;
@@ -9,6 +10,14 @@ use super::Memory;
 use super::Error;
 use std::hash::{Hash, Hasher};
 use std::fmt;
+use std::cmp;
+
+use libc;
+use num_cpus;
+
+/// Every native device gets a unique id, so that buffers can tell
+/// copies on different devices apart.
+static NEXT_ID: AtomicIsize = AtomicIsize::new(0);
 
 #[derive(Debug, Clone)]
 pub struct Device {
@@ -18,18 +27,40 @@ pub struct Device {
 
 struct Inner {
   hardware: Hardware,
-  pool: CpuPool
+  capabilities: device::DeviceCapabilities,
+  pool: CpuPool,
+  threads: usize,
+  allocated: Arc<AtomicUsize>,
+  // Held while queueing the tasks of a synchronize
+  barrier_lock: Mutex<()>
 }
 
 impl Device {
-  pub fn new(hardware: Hardware, mut builder: Builder) -> Device {
+  /// A device running on a pool made by `builder`, with one thread per
+  /// logical core. Any pool size set on `builder` is replaced.
+  pub fn new(hardware: Hardware, builder: Builder) -> Device {
+    Self::with_pool_size(hardware, builder, num_cpus::get())
+  }
+
+  /// A device running on a pool of `threads` workers made by `builder`.
+  pub fn with_pool_size(hardware: Hardware, mut builder: Builder, threads: usize) -> Device {
+    builder.pool_size(threads);
+    let total_memory = physical_memory();
     let inner = Arc::new(Inner {
       hardware: hardware,
-      pool: builder.create()
+      capabilities: device::DeviceCapabilities {
+        total_memory: total_memory,
+        max_single_allocation: cmp::min(total_memory, isize::MAX as usize),
+        logical_cores: num_cpus::get()
+      },
+      pool: builder.create(),
+      threads: threads,
+      allocated: Arc::new(AtomicUsize::new(0)),
+      barrier_lock: Mutex::new(())
     });
 
     Device {
-      id: 0,
+      id: NEXT_ID.fetch_add(1, Ordering::SeqCst),
       inner: inner
     }
   }
@@ -37,6 +68,62 @@ impl Device {
   pub fn pool(&self) -> &CpuPool {
     &self.inner.pool
   }
+
+  /// Number of worker threads in the pool, for splitting up work.
+  pub fn threads(&self) -> usize {
+    self.inner.threads
+  }
+
+  /// Run `f` on one of the device's workers, resolving to its result.
+  pub fn spawn<R, E, F>(&self, f: F) -> Box<Future<Item=R,Error=E>>
+    where F: FnOnce() -> Result<R, E> + Send + 'static,
+          R: Send + 'static,
+          E: Send + 'static {
+    Box::new(self.inner.pool.spawn_fn(f))
+  }
+
+  /// Resolves once all work queued on the pool before the call has
+  /// finished.
+  ///
+  /// A task is queued for every worker, and they only complete once
+  /// they all run at the same time, so every worker must be done with
+  /// the work ahead of them. Blocking on the result from one of the
+  /// device's own workers therefore never returns.
+  pub fn synchronize(&self) -> Box<Future<Item=(),Error=Error>> {
+    let barrier = Arc::new(Barrier::new(self.inner.threads));
+    let tasks: Vec<_> = {
+      // Tasks of two synchronizes must not interleave in the queue, or
+      // neither may get all the workers
+      let _queue = self.inner.barrier_lock.lock().unwrap_or_else(|e| e.into_inner());
+      (0..self.inner.threads).map(|_| {
+        let barrier = barrier.clone();
+        self.inner.pool.spawn_fn(move || -> Result<(), Error> {
+          barrier.wait();
+          Ok(())
+        })
+      }).collect()
+    };
+
+    Box::new(future::join_all(tasks).map(|_| ()))
+  }
+
+  /// Allocate memory whose address is a multiple of `align`, which
+  /// must be a power of two.
+  pub fn alloc_memory_aligned(&self, size: usize, align: usize) -> Result<Memory, Error> {
+    Memory::alloc_aligned(size, align).map(|m| m.tracked(self.inner.allocated.clone()))
+  }
+
+  /// Allocate page-locked memory, which makes for faster transfers to
+  /// and from other devices.
+  pub fn alloc_pinned(&self, size: usize) -> Result<Memory, Error> {
+    Memory::alloc_pinned(size).map(|m| m.tracked(self.inner.allocated.clone()))
+  }
+
+  /// Number of bytes currently allocated on this device. Memory is
+  /// returned to the device as soon as it is dropped.
+  pub fn allocated_bytes(&self) -> usize {
+    self.inner.allocated.load(Ordering::SeqCst)
+  }
 }
 
 impl device::Device for Device {
@@ -46,8 +133,9 @@ impl device::Device for Device {
 
   fn id(&self) -> isize { self.id }
   fn hardware(&self) -> &Self::H { &self.inner.hardware }
+  fn capabilities(&self) -> device::DeviceCapabilities { self.inner.capabilities }
   fn alloc_memory(&self, size: usize) -> Result<Self::M, Self::Error> {
-    Ok(Memory::alloc(size))
+    Memory::alloc(size).map(|m| m.tracked(self.inner.allocated.clone()))
   }
 
   fn sync_from_vec<T: Send + Copy + Sized + 'static>(&self,
@@ -59,6 +147,16 @@ impl device::Device for Device {
     }))
   }
 
+  fn sync_from_vec_at<T: Send + Copy + Sized + 'static>(&self,
+                                                        mut mem: Self::M,
+                                                        offset: usize,
+                                                        vec: Vec<T>) -> Box<Future<Item=Self::M,Error=Self::Error>> {
+    Box::new(self.inner.pool.spawn_fn(move || {
+      try!(mem.copy_from_at(offset, &vec));
+      Ok(mem)
+    }))
+  }
+
   fn sync_to_vec<T: Send + Copy + Sized + 'static>(&self,
                                                    mem: Self::M) -> Box<Future<Item=(Self::M, Vec<T>),Error=Self::Error>> {
     Box::new(self.inner.pool.spawn_fn(move || {
@@ -66,6 +164,34 @@ impl device::Device for Device {
       Ok((mem, vec))
     }))
   }
+
+  fn fill<T: Send + Copy + Sized + 'static>(&self,
+                                            mut mem: Self::M,
+                                            value: T) -> Box<Future<Item=Self::M,Error=Self::Error>> {
+    Box::new(self.inner.pool.spawn_fn(move || {
+      try!(mem.fill(value));
+      Ok(mem)
+    }))
+  }
+}
+
+#[cfg(unix)]
+fn physical_memory() -> usize {
+  let (pages, page_size) = unsafe {
+    (libc::sysconf(libc::_SC_PHYS_PAGES), libc::sysconf(libc::_SC_PAGESIZE))
+  };
+
+  if pages <= 0 || page_size <= 0 {
+    usize::MAX
+  } else {
+    (pages as usize).saturating_mul(page_size as usize)
+  }
+}
+
+// Unknown, allocations are left to fail on their own
+#[cfg(not(unix))]
+fn physical_memory() -> usize {
+  usize::MAX
 }
 
 impl PartialEq for Device {
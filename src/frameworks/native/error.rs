@@ -1,4 +1,27 @@
+use std::error;
+use std::fmt;
+
 #[derive(Debug, Clone, Copy)]
 pub enum Error {
-  OutOfMemory
+  OutOfMemory,
+  InvalidSize,
+  InvalidAlignment,
+  TypeMismatch,
+  PinFailed,
+  NoThreads
 }
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Error::OutOfMemory => write!(f, "out of host memory"),
+      Error::InvalidSize => write!(f, "memory size does not match the element type"),
+      Error::InvalidAlignment => write!(f, "invalid memory alignment"),
+      Error::TypeMismatch => write!(f, "memory holds elements of a different type"),
+      Error::PinFailed => write!(f, "could not lock memory into RAM"),
+      Error::NoThreads => write!(f, "a native device needs at least one worker thread")
+    }
+  }
+}
+
+impl error::Error for Error { }
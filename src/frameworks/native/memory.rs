@@ -1,67 +1,193 @@
 use std::result::Result;
+use std::alloc::{self, Layout};
 use std::mem;
 use std::ptr;
 use std::slice;
+use std::any::TypeId;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use super::Error;
 use memory;
 
-#[derive(Debug, Clone)]
+use libc;
+#[cfg(feature = "bytemuck")]
+use bytemuck;
+
+/// Alignment used when none is requested, enough for any primitive type
+pub const DEFAULT_ALIGN: usize = 16;
+
+#[derive(Debug)]
 pub struct Memory {
-  buf: Box<[u8]>
+  ptr: *mut u8,
+  layout: Layout,
+
+  // Usable bytes, pinned memory may be allocated beyond this
+  size: usize,
+
+  // Byte counter of the device that allocated this memory
+  allocated: Option<Arc<AtomicUsize>>,
+
+  // Element type the memory was allocated for, if known
+  element: Option<TypeId>,
+
+  // Locked into RAM, and so must be unlocked before being freed
+  pinned: bool
 }
 
+// Memory owns its allocation exclusively, just like a Box<[u8]>
+unsafe impl Send for Memory {}
+unsafe impl Sync for Memory {}
+
 impl Memory {
-  pub fn alloc(size: usize) -> Memory {
-    let vec: Vec<u8> = vec![0; size];
-    let buf = vec.into_boxed_slice();
+  pub fn alloc(size: usize) -> Result<Memory, Error> {
+    Self::alloc_aligned(size, DEFAULT_ALIGN)
+  }
 
-    Memory {
-      buf: buf
+  pub fn alloc_aligned(size: usize, align: usize) -> Result<Memory, Error> {
+    if !align.is_power_of_two() {
+      return Err(Error::InvalidAlignment)
     }
+
+    let layout = try!(Layout::from_size_align(size, align).map_err(|_| Error::OutOfMemory));
+    let ptr = if size == 0 {
+      // Zero sized allocations are not allowed, use an aligned dangling pointer
+      align as *mut u8
+    } else {
+      let ptr = unsafe { alloc::alloc_zeroed(layout) };
+      if ptr.is_null() {
+        return Err(Error::OutOfMemory)
+      }
+      ptr
+    };
+
+    Ok(Memory {
+      ptr: ptr,
+      layout: layout,
+      size: size,
+      allocated: None,
+      element: None,
+      pinned: false
+    })
   }
 
-  pub fn len(&self) -> usize { self.buf.len() }
+  /// Allocate page-locked memory, which is never swapped out.
+  ///
+  /// Pages are locked and unlocked whole, so the allocation is rounded
+  /// out to whole pages to keep it from sharing any with other memory.
+  pub fn alloc_pinned(size: usize) -> Result<Memory, Error> {
+    let page = page_size();
+    let bytes = try!(size.checked_add(page - 1).ok_or(Error::OutOfMemory)) & !(page - 1);
+
+    let mut memory = try!(Self::alloc_aligned(bytes, page));
+    memory.size = size;
+    if bytes != 0 && !lock_pages(memory.ptr, bytes) {
+      return Err(Error::PinFailed)
+    }
+
+    memory.pinned = true;
+    Ok(memory)
+  }
+
+  /// Count this memory against a device's allocated bytes until dropped.
+  pub fn tracked(mut self, allocated: Arc<AtomicUsize>) -> Memory {
+    allocated.fetch_add(self.len(), Ordering::SeqCst);
+    self.allocated = Some(allocated);
+    self
+  }
+
+  /// Only allow typed access to this memory as `T`, or as raw bytes.
+  pub fn tagged<T: 'static>(mut self) -> Memory {
+    self.tag::<T>();
+    self
+  }
+
+  pub fn tag<T: 'static>(&mut self) {
+    self.element = Some(TypeId::of::<T>());
+  }
+
+  pub fn len(&self) -> usize { self.size }
+
+  pub fn is_empty(&self) -> bool { self.size == 0 }
+
+  pub fn align(&self) -> usize { self.layout.align() }
+
+  pub fn is_pinned(&self) -> bool { self.pinned }
 
   pub fn as_ptr(&self) -> *const u8 {
-    self.buf.as_ptr()
+    self.ptr
   }
 
   pub fn as_mut_ptr(&mut self) -> *mut u8 {
-    self.buf.as_mut_ptr()
+    self.ptr
+  }
+
+  // Number of `T`s in this memory, as long as it holds a whole number
+  // of them at a suitable alignment
+  fn element_count<T: Sized + 'static>(&self) -> Result<usize, Error> {
+    let id = TypeId::of::<T>();
+    match self.element {
+      Some(element) if element != id && id != TypeId::of::<u8>() => return Err(Error::TypeMismatch),
+      _ => ()
+    }
+
+    let size = mem::size_of::<T>();
+    if size == 0 || self.len() % size != 0 {
+      return Err(Error::InvalidSize)
+    }
+
+    if self.len() != 0 && self.ptr as usize % mem::align_of::<T>() != 0 {
+      return Err(Error::InvalidAlignment)
+    }
+
+    Ok(self.len() / size)
   }
 
-  pub fn try_as_slice<T: Sized + Copy>(&self) -> Result<&[T], Error> {
-    if self.len() % mem::size_of::<T>() != 0 {
-      // TODO: better error
-      return Err(Error::OutOfMemory)
+  pub fn try_as_slice<T: Sized + Copy + 'static>(&self) -> Result<&[T], Error> {
+    let count = try!(self.element_count::<T>());
+    if count == 0 {
+      return Ok(&[])
     }
 
     unsafe {
       let p = self.as_ptr();
       let pt = mem::transmute::<*const u8, *const T>(p);
-      Ok(slice::from_raw_parts(pt, self.len() / mem::size_of::<T>()))
+      Ok(slice::from_raw_parts(pt, count))
     }
   }
 
-  pub fn try_as_mut_slice<T: Sized + Copy>(&mut self) -> Result<&mut [T], Error> {
-    if self.len() % mem::size_of::<T>() != 0 {
-      // TODO: better error
-      return Err(Error::OutOfMemory)
+  pub fn try_as_mut_slice<T: Sized + Copy + 'static>(&mut self) -> Result<&mut [T], Error> {
+    let count = try!(self.element_count::<T>());
+    if count == 0 {
+      return Ok(&mut [])
     }
 
     unsafe {
       let p = self.as_mut_ptr();
       let pt = mem::transmute::<*mut u8, *mut T>(p);
-      Ok(slice::from_raw_parts_mut(pt, self.len() / mem::size_of::<T>()))
+      Ok(slice::from_raw_parts_mut(pt, count))
     }
   }
 
+  /// Like `try_as_slice`, but checked by `bytemuck` instead of the
+  /// element type the memory was allocated for. Any `Pod` type may be
+  /// read from any memory of a suitable size and alignment.
+  #[cfg(feature = "bytemuck")]
+  pub fn try_as_pod_slice<T: bytemuck::Pod>(&self) -> Result<&[T], Error> {
+    let bytes = unsafe { slice::from_raw_parts(self.as_ptr(), self.len()) };
+    bytemuck::try_cast_slice(bytes).map_err(pod_error)
+  }
+
+  #[cfg(feature = "bytemuck")]
+  pub fn try_as_pod_mut_slice<T: bytemuck::Pod>(&mut self) -> Result<&mut [T], Error> {
+    let bytes = unsafe { slice::from_raw_parts_mut(self.as_mut_ptr(), self.len()) };
+    bytemuck::try_cast_slice_mut(bytes).map_err(pod_error)
+  }
+
   pub fn copy_from<T: Sized + Copy>(&mut self,
                                     vs: &[T]) -> Result<(), Error> {
     if self.len() != vs.len() * mem::size_of::<T>() {
-      // TODO: better error
-      return Err(Error::OutOfMemory)
+      return Err(Error::InvalidSize)
     }
 
     unsafe {
@@ -72,21 +198,110 @@ impl Memory {
     }
   }
 
-  pub fn into_vec<T: Sized + Copy>(self) -> Result<Vec<T>, Error> {
-    if self.len() % mem::size_of::<T>() != 0 {
-      // TODO: better error
-      return Err(Error::OutOfMemory)
+  pub fn copy_from_at<T: Sized + Copy + 'static>(&mut self,
+                                       offset: usize,
+                                       vs: &[T]) -> Result<(), Error> {
+    let s: &mut [T] = try!(self.try_as_mut_slice());
+    if offset.checked_add(vs.len()).map_or(true, |end| end > s.len()) {
+      return Err(Error::InvalidSize)
+    }
+
+    s[offset..(offset + vs.len())].copy_from_slice(vs);
+    Ok(())
+  }
+
+  pub fn zero(&mut self) {
+    unsafe {
+      ptr::write_bytes(self.as_mut_ptr(), 0, self.len());
+    }
+  }
+
+  pub fn fill<T: Sized + Copy + 'static>(&mut self, value: T) -> Result<(), Error> {
+    for v in try!(self.try_as_mut_slice::<T>()).iter_mut() {
+      *v = value;
     }
 
+    Ok(())
+  }
+
+  pub fn into_vec<T: Sized + Copy + 'static>(self) -> Result<Vec<T>, Error> {
+    let vs: &[T] = try!(self.try_as_slice());
+    Ok(vs.to_vec())
+  }
+}
+
+#[cfg(feature = "bytemuck")]
+fn pod_error(err: bytemuck::PodCastError) -> Error {
+  match err {
+    bytemuck::PodCastError::TargetAlignmentGreaterAndInputNotAligned |
+    bytemuck::PodCastError::AlignmentMismatch => Error::InvalidAlignment,
+    _ => Error::InvalidSize
+  }
+}
+
+#[cfg(unix)]
+fn page_size() -> usize {
+  match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
+    n if n > 0 => n as usize,
+    _ => 4096
+  }
+}
+
+#[cfg(not(unix))]
+fn page_size() -> usize { 4096 }
+
+#[cfg(unix)]
+fn lock_pages(ptr: *mut u8, size: usize) -> bool {
+  unsafe { libc::mlock(ptr as *const libc::c_void, size) == 0 }
+}
+
+#[cfg(unix)]
+fn unlock_pages(ptr: *mut u8, size: usize) {
+  unsafe { libc::munlock(ptr as *const libc::c_void, size); }
+}
+
+// No way to lock pages yet
+#[cfg(not(unix))]
+fn lock_pages(_ptr: *mut u8, _size: usize) -> bool {
+  false
+}
+
+#[cfg(not(unix))]
+fn unlock_pages(_ptr: *mut u8, _size: usize) { }
+
+/// Copies are never pinned.
+impl Clone for Memory {
+  fn clone(&self) -> Memory {
+    let mut memory = Self::alloc_aligned(self.len(), self.align()).
+      unwrap_or_else(|_| alloc::handle_alloc_error(self.layout));
     unsafe {
-      let mut buf: Box<[u8]> = self.buf.clone();
-      let buf_ptr = mem::transmute::<*mut u8, *mut T>(buf.as_mut_ptr());
-      let len = self.len() / mem::size_of::<T>();
-      let vec = Vec::from_raw_parts(buf_ptr, len, len);
-      mem::forget(buf);
-      Ok(vec)
+      ptr::copy_nonoverlapping(self.as_ptr(), memory.as_mut_ptr(), self.len());
+    }
+    memory.element = self.element;
+
+    match self.allocated {
+      Some(ref allocated) => memory.tracked(allocated.clone()),
+      None => memory
     }
   }
 }
 
-impl memory::Memory for Memory { }
+impl Drop for Memory {
+  fn drop(&mut self) {
+    if let Some(ref allocated) = self.allocated {
+      allocated.fetch_sub(self.len(), Ordering::SeqCst);
+    }
+
+    if self.pinned && self.layout.size() != 0 {
+      unlock_pages(self.ptr, self.layout.size());
+    }
+
+    if self.layout.size() != 0 {
+      unsafe { alloc::dealloc(self.ptr, self.layout) }
+    }
+  }
+}
+
+impl memory::Memory for Memory {
+  fn byte_len(&self) -> usize { self.len() }
+}
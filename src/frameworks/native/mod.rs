@@ -13,7 +13,7 @@ pub use self::memory::Memory;
 pub use self::error::Error;
 pub use self::backend::Backend;
 
-use framework::Framework as IFramework;
+use framework::{Framework as IFramework, OpKind};
 
 pub struct Framework { }
 
@@ -25,6 +25,22 @@ impl Framework {
   pub fn default_device(&self) -> Device {
     self.new_device(&self.default_hardware()).unwrap()
   }
+
+  /// A device running its work on exactly `threads` worker threads,
+  /// rather than one per logical core. Fails with `NoThreads` for zero.
+  pub fn new_device_with_threads(&self, hardware: &Hardware, threads: usize) -> Result<Device, Error> {
+    if threads == 0 {
+      return Err(Error::NoThreads)
+    }
+
+    Ok(Device::with_pool_size(hardware.clone(), Self::builder(hardware), threads))
+  }
+
+  fn builder(hardware: &Hardware) -> Builder {
+    let mut builder = Builder::new();
+    builder.name_prefix(hardware.name());
+    builder
+  }
 }
 
 impl IFramework for Framework {
@@ -44,9 +60,12 @@ impl IFramework for Framework {
   }
 
   fn new_device(&self, hardware: &Self::H) -> Result<Self::D, Self::Error> {
-    let mut builder = Builder::new();
-    builder.name_prefix(hardware.name());
+    Ok(Device::new(hardware.clone(), Self::builder(hardware)))
+  }
 
-    Ok(Device::new(hardware.clone(), builder))
+  fn supported_ops(&self) -> &[OpKind] {
+    &[OpKind::Dot, OpKind::Gemm, OpKind::Gemv, OpKind::Axpy, OpKind::Scal, OpKind::Norm,
+      OpKind::Reduce, OpKind::ArgReduce, OpKind::Activation, OpKind::Softmax,
+      OpKind::Scalar, OpKind::Clamp]
   }
 }
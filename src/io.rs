@@ -0,0 +1,235 @@
+//! Reading and writing buffers in the NumPy `.npy` format.
+//!
+//! Only C ordered arrays of little-endian primitive elements are
+//! supported.
+
+use std::error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Write, BufReader, BufWriter};
+use std::mem;
+use std::path::Path;
+use buffer::{self, Buffer, LockedBuffer};
+use frameworks::native;
+
+const MAGIC: &'static [u8] = b"\x93NUMPY";
+
+// Data starts on a multiple of this many bytes
+const HEADER_ALIGN: usize = 64;
+
+#[derive(Debug)]
+pub enum Error {
+  Io(io::Error),
+  Buffer(buffer::Error),
+  InvalidHeader,
+  UnsupportedVersion { major: u8, minor: u8 },
+  FortranOrder,
+  DtypeMismatch { expected: &'static str, found: String },
+  ShapeMismatch { shape: Vec<usize>, size: usize }
+}
+
+impl From<io::Error> for Error {
+  fn from(err: io::Error) -> Error { Error::Io(err) }
+}
+
+impl From<buffer::Error> for Error {
+  fn from(err: buffer::Error) -> Error { Error::Buffer(err) }
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Error::Io(ref err) => write!(f, "i/o error: {}", err),
+      Error::Buffer(ref err) => write!(f, "buffer error: {}", err),
+      Error::InvalidHeader => write!(f, "invalid npy header"),
+      Error::UnsupportedVersion { major, minor } => write!(f, "unsupported npy version {}.{}", major, minor),
+      Error::FortranOrder => write!(f, "fortran ordered arrays are not supported"),
+      Error::DtypeMismatch { expected, ref found } => {
+        write!(f, "expected dtype {}, found {}", expected, found)
+      },
+      Error::ShapeMismatch { ref shape, size } => {
+        write!(f, "shape {:?} does not match a buffer of {} elements", shape, size)
+      }
+    }
+  }
+}
+
+impl error::Error for Error {
+  fn source(&self) -> Option<&(error::Error + 'static)> {
+    match *self {
+      Error::Io(ref err) => Some(err),
+      Error::Buffer(ref err) => Some(err),
+      _ => None
+    }
+  }
+}
+
+/// Element types that can be stored in a `.npy` file.
+pub trait Element: Send + Copy + Sized + 'static {
+  /// NumPy type descriptor, e.g. `<f4`.
+  fn descr() -> &'static str;
+  fn write_le(&self, out: &mut Vec<u8>);
+  fn read_le(bytes: &[u8]) -> Self;
+}
+
+macro_rules! impl_element {
+  ($t:ty, $descr:expr) => {
+    impl Element for $t {
+      fn descr() -> &'static str { $descr }
+
+      fn write_le(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+      }
+
+      fn read_le(bytes: &[u8]) -> $t {
+        let mut le = [0u8; mem::size_of::<$t>()];
+        le.copy_from_slice(bytes);
+        <$t>::from_le_bytes(le)
+      }
+    }
+  }
+}
+
+impl_element!(f32, "<f4");
+impl_element!(f64, "<f8");
+impl_element!(i32, "<i4");
+impl_element!(i64, "<i8");
+impl_element!(u8, "|u1");
+
+/// Write the latest contents of `buffer` to `path` as an array of
+/// `shape`. The latest copy must be on a native device.
+pub fn save_npy<T: Element, P: AsRef<Path>>(path: P,
+                                            buffer: &LockedBuffer<T>,
+                                            shape: &[usize]) -> Result<(), Error> {
+  if elements(shape) != Some(buffer.size()) {
+    return Err(Error::ShapeMismatch { shape: shape.to_vec(), size: buffer.size() })
+  }
+
+  let view = try!(buffer.slice(0..buffer.size()));
+  let vs = try!(view.try_as_slice());
+
+  let mut data = Vec::with_capacity(vs.len() * mem::size_of::<T>());
+  for v in vs.iter() {
+    v.write_le(&mut data);
+  }
+
+  let mut out = BufWriter::new(try!(File::create(path)));
+  try!(out.write_all(&header(T::descr(), shape)));
+  try!(out.write_all(&data));
+  try!(out.flush());
+  Ok(())
+}
+
+/// Read an array from `path` into a new buffer on `dev`, along with
+/// its shape.
+pub fn load_npy<T: Element, P: AsRef<Path>>(path: P,
+                                            dev: &native::Device) -> Result<(Buffer<T>, Vec<usize>), Error> {
+  let mut input = BufReader::new(try!(File::open(path)));
+
+  let mut preamble = [0u8; 8];
+  try!(input.read_exact(&mut preamble));
+  if &preamble[..6] != MAGIC {
+    return Err(Error::InvalidHeader)
+  }
+
+  let header_len = match (preamble[6], preamble[7]) {
+    (1, _) => {
+      let mut len = [0u8; 2];
+      try!(input.read_exact(&mut len));
+      u16::from_le_bytes(len) as usize
+    },
+    (2, _) | (3, _) => {
+      let mut len = [0u8; 4];
+      try!(input.read_exact(&mut len));
+      u32::from_le_bytes(len) as usize
+    },
+    (major, minor) => return Err(Error::UnsupportedVersion { major: major, minor: minor })
+  };
+
+  let mut header = vec![0u8; header_len];
+  try!(input.read_exact(&mut header));
+  let header = try!(String::from_utf8(header).map_err(|_| Error::InvalidHeader));
+
+  let descr = try!(header_value(&header, "descr").ok_or(Error::InvalidHeader));
+  let descr = descr.trim_matches(|c| c == '\'' || c == '"');
+  if descr != T::descr() {
+    return Err(Error::DtypeMismatch { expected: T::descr(), found: descr.to_string() })
+  }
+
+  let shape = try!(header_value(&header, "shape").and_then(parse_shape).ok_or(Error::InvalidHeader));
+  match header_value(&header, "fortran_order") {
+    Some("False") => (),
+    Some("True") if shape.len() < 2 => (),
+    Some("True") => return Err(Error::FortranOrder),
+    _ => return Err(Error::InvalidHeader)
+  }
+
+  // The shape comes straight from the file, so check that the data it
+  // describes is actually there before allocating room for it
+  let size = mem::size_of::<T>();
+  let bytes = try!(elements(&shape).and_then(|n| n.checked_mul(size)).ok_or(Error::InvalidHeader));
+  let start = (preamble.len() + if preamble[6] == 1 { 2 } else { 4 } + header_len) as u64;
+  let file_len = try!(input.get_ref().metadata()).len();
+  if file_len.saturating_sub(start) < bytes as u64 {
+    return Err(Error::InvalidHeader)
+  }
+
+  let mut data = vec![0u8; bytes];
+  try!(input.read_exact(&mut data));
+
+  let vs = data.chunks(size).map(T::read_le).collect();
+  let buffer = try!(Buffer::from_vec_native(dev, vs));
+  Ok((buffer, shape))
+}
+
+// Number of elements in an array of `shape`, unless it overflows
+fn elements(shape: &[usize]) -> Option<usize> {
+  shape.iter().try_fold(1usize, |n, &d| n.checked_mul(d))
+}
+
+fn header(descr: &str, shape: &[usize]) -> Vec<u8> {
+  let shape = match shape.len() {
+    1 => format!("({},)", shape[0]),
+    _ => format!("({})", shape.iter().map(|d| d.to_string()).collect::<Vec<_>>().join(", "))
+  };
+  let dict = format!("{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}", descr, shape);
+
+  // Magic, version and length come first, the dict is padded with
+  // spaces and ends with a newline
+  let unpadded = MAGIC.len() + 4 + dict.len() + 1;
+  let len = dict.len() + 1 + (HEADER_ALIGN - unpadded % HEADER_ALIGN) % HEADER_ALIGN;
+
+  let mut out = Vec::with_capacity(MAGIC.len() + 4 + len);
+  out.extend_from_slice(MAGIC);
+  out.extend_from_slice(&[1, 0]);
+  out.extend_from_slice(&(len as u16).to_le_bytes());
+  out.extend_from_slice(dict.as_bytes());
+  while out.len() < MAGIC.len() + 4 + len - 1 {
+    out.push(b' ');
+  }
+  out.push(b'\n');
+  out
+}
+
+// The raw text of a value in the header dict, which holds no nested
+// dicts and only parenthesized tuples
+fn header_value<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+  let start = header.find(&format!("'{}'", key)).map(|i| i + key.len() + 2)?;
+  let rest = header[start..].trim_start();
+  let rest = rest.strip_prefix(':')?.trim_start();
+  let end = if rest.starts_with('(') {
+    rest.find(')')? + 1
+  } else {
+    rest.find(|c| c == ',' || c == '}')?
+  };
+  Some(rest[..end].trim())
+}
+
+fn parse_shape(value: &str) -> Option<Vec<usize>> {
+  let dims = value.strip_prefix('(')?.strip_suffix(')')?;
+  dims.split(',').
+    map(|d| d.trim()).
+    filter(|d| !d.is_empty()).
+    map(|d| d.parse().ok()).
+    collect()
+}
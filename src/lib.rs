@@ -7,6 +7,23 @@
 extern crate futures;
 extern crate futures_cpupool;
 extern crate spin;
+extern crate num_cpus;
+extern crate libc;
+extern crate rand;
+
+#[cfg(feature = "serialize")]
+extern crate serde;
+#[cfg(feature = "serialize")]
+#[macro_use]
+extern crate serde_derive;
+#[cfg(all(test, feature = "serialize"))]
+extern crate serde_json;
+#[cfg(feature = "ndarray")]
+extern crate ndarray;
+#[cfg(feature = "bytemuck")]
+extern crate bytemuck;
+#[cfg(feature = "half")]
+extern crate half;
 
 pub mod backend;
 pub mod hardware;
@@ -16,16 +33,36 @@ pub mod device;
 pub mod buffer;
 pub mod frameworks;
 pub mod vault;
+pub mod num;
+pub mod cancel;
+pub mod stream;
+#[cfg(feature = "native")]
+pub mod io;
+#[cfg(feature = "native")]
+pub mod pool;
 
 pub use backend::Backend;
 pub use hardware::Hardware;
-pub use framework::Framework;
+pub use framework::{Framework, OpKind};
 pub use memory::Memory;
-pub use device::Device;
-pub use buffer::{LockedBuffer, Buffer, BufferDevice};
+pub use device::{Device, DeviceCapabilities};
+pub use buffer::{LockedBuffer, Buffer, BufferDevice, BufferId, join_buffers};
+#[cfg(feature = "serialize")]
+pub use buffer::BufferSnapshot;
+#[cfg(feature = "native")]
+pub use buffer::{BufferView, BufferViewMut};
+#[cfg(feature = "native")]
+pub use pool::{BufferPool, PooledBuffer};
 pub use vault::Vault;
+pub use cancel::{CancelToken, Cancellable};
+pub use stream::{map_op, MapOp};
+pub use num::{Zero, One, Numeric, Float};
+#[cfg(feature = "half")]
+pub use half::f16;
 
-pub use frameworks::native;
+pub use frameworks::{native, enumerate};
+#[cfg(feature = "cuda")]
+pub use frameworks::cuda;
 
 #[cfg(test)]
 mod test {
@@ -45,4 +82,1036 @@ mod test {
 
     assert_eq!(nv, vec![23.0, 45.5, 54.2, 42.0]);
   }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_sync_between_devices() {
+    let framework = native::Framework::new();
+    let dev_a = framework.default_device();
+    let dev_b = framework.default_device();
+    assert!(dev_a != dev_b);
+
+    let buf: Buffer<f32> = Buffer::new(&dev_a, 3).unwrap();
+    let lbuf = buf.lock().and_then(|b| b.sync_from_vec(vec![1.5, 2.5, 3.5])).
+      and_then(|b| b.sync(&dev_b)).wait().unwrap();
+
+    assert!(lbuf.native_memory(&dev_a).is_ok());
+    assert!(lbuf.native_memory(&dev_b).is_ok());
+    assert_eq!(lbuf.sync_to_vec().wait().unwrap(), vec![1.5, 2.5, 3.5]);
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_fill() {
+    let backend = native::Backend::default();
+    let dev = backend.device();
+
+    let buf: Buffer<f32> = Buffer::new(dev, 3).unwrap();
+    let v = buf.lock().and_then(|b| b.fill(4.5)).
+      and_then(|b| b.sync_to_vec()).wait().unwrap();
+    assert_eq!(v, vec![4.5, 4.5, 4.5]);
+
+    let v = Buffer::filled_native(dev, 2, 1.5f32).
+      and_then(|b| b.lock()).and_then(|b| b.sync_to_vec()).wait().unwrap();
+    assert_eq!(v, vec![1.5, 1.5]);
+
+    let empty: Buffer<f32> = Buffer::new(dev, 0).unwrap();
+    let v = empty.lock().and_then(|b| b.fill(4.5)).
+      and_then(|b| b.sync_to_vec()).wait().unwrap();
+    assert!(v.is_empty());
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_zeros() {
+    let backend = native::Backend::default();
+    let buf: Buffer<f32> = Buffer::zeros_native(backend.device(), 5).unwrap();
+    let v = buf.lock().and_then(|b| b.sync_to_vec()).wait().unwrap();
+
+    assert_eq!(v, vec![0.0; 5]);
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_slice() {
+    let backend = native::Backend::default();
+    let buf: Buffer<f32> = Buffer::from_vec_native(backend.device(), vec![1.0, 2.0, 3.0, 4.0]).unwrap();
+    let mut lbuf = buf.try_lock().unwrap();
+
+    assert_eq!(lbuf.slice(1..3).unwrap().try_as_slice().unwrap(), &[2.0, 3.0]);
+    assert!(lbuf.slice(3..5).is_err());
+
+    lbuf.slice_mut(2..4).unwrap().try_as_mut_slice().unwrap()[0] = 8.0;
+    assert_eq!(lbuf.sync_to_vec().wait().unwrap(), vec![1.0, 2.0, 8.0, 4.0]);
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_resize() {
+    let backend = native::Backend::default();
+    let buf: Buffer<f32> = Buffer::from_vec_native(backend.device(), vec![1.0, 2.0, 3.0]).unwrap();
+
+    let lbuf = buf.lock().and_then(|b| b.resize(5, 9.0)).wait().unwrap();
+    assert_eq!(lbuf.size(), 5);
+    assert_eq!(lbuf.sync_to_vec().wait().unwrap(), vec![1.0, 2.0, 3.0, 9.0, 9.0]);
+
+    let lbuf = buf.lock().and_then(|b| b.resize(2, 9.0)).wait().unwrap();
+    assert_eq!(lbuf.size(), 2);
+    assert_eq!(lbuf.sync_to_vec().wait().unwrap(), vec![1.0, 2.0]);
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_deep_copy() {
+    let backend = native::Backend::default();
+    let dev = backend.device();
+    let buf: Buffer<f32> = Buffer::from_vec_native(dev, vec![1.0, 2.0, 3.0]).unwrap();
+
+    let (_, copy) = buf.lock().and_then(|b| b.deep_copy(dev)).wait().unwrap();
+    let v = copy.lock().and_then(|b| b.fill(7.0)).
+      and_then(|b| b.sync_to_vec()).wait().unwrap();
+    assert_eq!(v, vec![7.0, 7.0, 7.0]);
+
+    let v = buf.lock().and_then(|b| b.sync_to_vec()).wait().unwrap();
+    assert_eq!(v, vec![1.0, 2.0, 3.0]);
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_get_set() {
+    let backend = native::Backend::default();
+    let dev = backend.device();
+    let other = native::Framework::new().default_device();
+    let buf: Buffer<f32> = Buffer::from_vec_native(dev, vec![1.0, 2.0, 3.0]).unwrap();
+    let mut lbuf = buf.try_lock().unwrap();
+
+    assert_eq!(lbuf.get(dev, 1).unwrap(), 2.0);
+    lbuf.set(dev, 1, 5.0).unwrap();
+    assert_eq!(lbuf.get(dev, 1).unwrap(), 5.0);
+
+    assert!(match lbuf.get(dev, 3) { Err(buffer::Error::OutOfBounds) => true, _ => false });
+    assert!(match lbuf.set(dev, 3, 1.0) { Err(buffer::Error::OutOfBounds) => true, _ => false });
+    assert!(match lbuf.get(&other, 0) { Err(buffer::Error::InvalidDevice) => true, _ => false });
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_reinterpret() {
+    let backend = native::Backend::default();
+    let dev = backend.device();
+
+    let buf: Buffer<f32> = Buffer::from_vec_native(dev, vec![1.0, 2.0, 3.0, 4.0]).unwrap();
+
+    // The memory can not be taken from under another handle, which
+    // still sees the same contents afterwards
+    assert!(match buf.try_lock().unwrap().reinterpret::<[f32; 2]>() {
+      Err(buffer::Error::SharedBuffer) => true,
+      _ => false
+    });
+    assert_eq!(buf.lock().and_then(|b| b.sync_to_vec()).wait().unwrap(), vec![1.0, 2.0, 3.0, 4.0]);
+
+    let lbuf = buf.try_lock().unwrap();
+    drop(buf);
+    let lbuf = lbuf.reinterpret::<[f32; 2]>().unwrap();
+    assert_eq!(lbuf.size(), 2);
+    assert_eq!(lbuf.sync_to_vec().wait().unwrap(), vec![[1.0, 2.0], [3.0, 4.0]]);
+
+    let lbuf = Buffer::<f32>::from_vec_native(dev, vec![1.0, 2.0, 3.0]).unwrap().try_lock().unwrap();
+    assert!(match lbuf.reinterpret::<[f32; 2]>() {
+      Err(buffer::Error::InvalidElementSize { bytes: 12, element: 8 }) => true,
+      _ => false
+    });
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_sync_to_slice() {
+    let backend = native::Backend::default();
+    let buf: Buffer<f32> = Buffer::from_vec_native(backend.device(), vec![1.0, 2.0]).unwrap();
+
+    let mut dst = [0.0, 0.0, 9.0];
+    let lbuf = buf.lock().and_then(|b| b.sync_to_slice(&mut dst)).wait().unwrap();
+    assert_eq!(dst, [1.0, 2.0, 9.0]);
+
+    let mut small = [0.0];
+    assert!(lbuf.sync_to_slice(&mut small).wait().is_err());
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_frees_memory() {
+    let backend = native::Backend::default();
+    let dev = backend.device();
+    let baseline = dev.allocated_bytes();
+
+    let bufs: Vec<Buffer<f64>> = (0..100).map(|_| Buffer::new(dev, 16).unwrap()).collect();
+    assert_eq!(dev.allocated_bytes(), baseline + 100 * 16 * 8);
+
+    drop(bufs);
+    assert_eq!(dev.allocated_bytes(), baseline);
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_write_invalidates_copies() {
+    let framework = native::Framework::new();
+    let dev_a = framework.default_device();
+    let dev_b = framework.default_device();
+
+    let buf: Buffer<f32> = Buffer::from_vec_native(&dev_a, vec![1.0, 2.0]).unwrap();
+    let mut lbuf = buf.lock().and_then(|b| b.sync(&dev_b)).wait().unwrap();
+
+    lbuf.set(&dev_a, 0, 5.0).unwrap();
+    assert!(lbuf.native_memory(&dev_b).is_err());
+
+    let lbuf = lbuf.sync(&dev_b).wait().unwrap();
+    assert_eq!(lbuf.get(&dev_b, 0).unwrap(), 5.0);
+    assert_eq!(lbuf.sync_to_vec().wait().unwrap(), vec![5.0, 2.0]);
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_iter() {
+    let backend = native::Backend::default();
+    let dev = backend.device();
+    let buf: Buffer<f32> = Buffer::from_vec_native(dev, vec![1.0, 2.0, 3.0]).unwrap();
+    let mut lbuf = buf.try_lock().unwrap();
+
+    assert_eq!(lbuf.iter(dev).unwrap().fold(0.0, |acc, v| acc + v), 6.0);
+
+    for v in lbuf.iter_mut(dev).unwrap() {
+      *v *= 2.0;
+    }
+    assert_eq!(lbuf.iter(dev).unwrap().cloned().collect::<Vec<f32>>(), vec![2.0, 4.0, 6.0]);
+  }
+
+  #[test]
+  #[cfg(all(feature = "native", feature = "serialize"))]
+  fn test_native_serialize_round_trip() {
+    let backend = native::Backend::default();
+    let dev = backend.device();
+    let buf: Buffer<f32> = Buffer::from_vec_native(dev, vec![1.0, 2.5, 3.0]).unwrap();
+
+    let snapshot = buf.lock().and_then(|b| b.snapshot()).wait().unwrap();
+    let json = serde_json::to_string(&snapshot).unwrap();
+    let snapshot: BufferSnapshot<f32> = serde_json::from_str(&json).unwrap();
+
+    let restored: Buffer<f32> = Buffer::from_serialized(dev, snapshot).unwrap();
+    let v = restored.lock().and_then(|b| b.sync_to_vec()).wait().unwrap();
+    assert_eq!(v, vec![1.0, 2.5, 3.0]);
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_sync_from_vec_at() {
+    let backend = native::Backend::default();
+    let buf: Buffer<f32> = Buffer::zeros_native(backend.device(), 5).unwrap();
+
+    let v = buf.lock().and_then(|b| b.sync_from_vec_at(0, vec![1.0, 2.0])).
+      and_then(|b| b.sync_from_vec_at(3, vec![4.0, 5.0])).
+      and_then(|b| b.sync_to_vec()).wait().unwrap();
+    assert_eq!(v, vec![1.0, 2.0, 0.0, 4.0, 5.0]);
+
+    let r = buf.lock().and_then(|b| b.sync_from_vec_at(4, vec![1.0, 2.0])).wait();
+    assert!(match r { Err(buffer::Error::OutOfBounds) => true, _ => false });
+
+    let r = buf.lock().and_then(|b| b.sync_from_vec_at(usize::MAX, vec![1.0])).wait();
+    assert!(match r { Err(buffer::Error::OutOfBounds) => true, _ => false });
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_memory_byte_len() {
+    let backend = native::Backend::default();
+    let mem = backend.device().alloc_memory(6 * 4).unwrap();
+
+    assert_eq!(mem.byte_len(), 6 * 4);
+    assert_eq!(Memory::len(&mem), 6 * 4);
+    assert!(!mem.is_empty());
+    assert!(backend.device().alloc_memory(0).unwrap().is_empty());
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_aligned_alloc() {
+    let backend = native::Backend::default();
+    let dev = backend.device();
+
+    let mem = dev.alloc_memory_aligned(100, 64).unwrap();
+    assert_eq!(mem.as_ptr() as usize % 64, 0);
+    assert!(dev.alloc_memory_aligned(100, 3).is_err());
+
+    let buf: Buffer<f32> = Buffer::new_aligned(dev, 8, 32).unwrap();
+    let lbuf = buf.try_lock().unwrap();
+    assert_eq!(lbuf.native_memory(dev).unwrap().as_ptr() as usize % 32, 0);
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_pool_survives_panic() {
+    use std::panic;
+
+    let builder = futures_cpupool::Builder::new();
+    let dev = native::Device::with_pool_size(native::Hardware::new(), builder, 1);
+
+    let bad = dev.pool().spawn_fn(|| -> Result<(), ()> { panic!("bad kernel") });
+    assert!(panic::catch_unwind(panic::AssertUnwindSafe(|| bad.wait())).is_err());
+
+    let good = dev.pool().spawn_fn(|| -> Result<u32, ()> { Ok(42) });
+    assert_eq!(good.wait(), Ok(42));
+  }
+
+  #[test]
+  #[cfg(all(feature = "native", feature = "cuda"))]
+  fn test_cuda_round_trip() {
+    // Nothing to test on machines without a GPU
+    let gpu = match cuda::Backend::new() {
+      Ok(backend) => backend,
+      Err(_) => return
+    };
+    let cpu = native::Backend::default();
+
+    let buf: Buffer<f32> = Buffer::new(gpu.device(), 3).unwrap();
+    let lbuf = buf.lock().and_then(|b| b.sync_from_vec(vec![1.0, 2.0, 3.0])).
+      and_then(|b| b.sync(cpu.device())).wait().unwrap();
+
+    assert_eq!(lbuf.native_memory(cpu.device()).unwrap().try_as_slice::<f32>().unwrap(), &[1.0, 2.0, 3.0]);
+    assert_eq!(lbuf.sync_to_vec().wait().unwrap(), vec![1.0, 2.0, 3.0]);
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_enumerate_devices() {
+    let devices = enumerate();
+    assert_eq!(devices, enumerate());
+    assert!(devices.iter().any(|d| match *d {
+      BufferDevice::Native(_) => true,
+      #[allow(unreachable_patterns)]
+      _ => false
+    }));
+
+    let backend = native::Backend::default();
+    assert_eq!(backend.devices(), vec![BufferDevice::Native(backend.device().clone())]);
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_capabilities() {
+    let backend = native::Backend::default();
+    let caps = backend.device().capabilities();
+    assert!(caps.total_memory > 0);
+    assert!(caps.max_single_allocation > 0);
+    assert!(caps.logical_cores > 0);
+
+    match Buffer::<u8>::new(backend.device(), caps.max_single_allocation + 1) {
+      Err(buffer::Error::AllocationTooLarge { bytes, max }) => {
+        assert_eq!(bytes, caps.max_single_allocation + 1);
+        assert_eq!(max, caps.max_single_allocation);
+      },
+      _ => panic!("expected the allocation to be refused")
+    }
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_with_threads() {
+    use std::sync::{Arc, Barrier};
+
+    let backend = native::Backend::with_threads(2).unwrap();
+    let dev = backend.device();
+
+    // Both tasks only finish if they run at the same time
+    let barrier = Arc::new(Barrier::new(2));
+    let tasks: Vec<_> = (0..2).map(|i| {
+      let barrier = barrier.clone();
+      dev.pool().spawn_fn(move || -> Result<usize, ()> {
+        barrier.wait();
+        Ok(i)
+      })
+    }).collect();
+    assert_eq!(futures::future::join_all(tasks).wait(), Ok(vec![0, 1]));
+
+    let buf: Buffer<f32> = Buffer::new(dev, 2).unwrap();
+    let v = buf.lock().and_then(|b| b.sync_from_vec(vec![1.0, 2.0])).
+      and_then(|b| b.sync_to_vec()).wait().unwrap();
+    assert_eq!(v, vec![1.0, 2.0]);
+
+    assert!(match native::Backend::with_threads(0) { Err(native::Error::NoThreads) => true, _ => false });
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_new_async() {
+    let backend = native::Backend::default();
+    let v = Buffer::<f32>::new_async(backend.device(), 3).
+      and_then(|buf| buf.lock().and_then(|b| b.sync_from_vec(vec![1.0, 2.0, 3.0]))).
+      and_then(|b| b.sync_to_vec()).wait().unwrap();
+    assert_eq!(v, vec![1.0, 2.0, 3.0]);
+  }
+
+  #[test]
+  fn test_error_display() {
+    use std::error::Error;
+
+    assert_eq!(buffer::Error::InvalidBroadcast.to_string(), "shapes cannot be broadcast together");
+
+    let err = buffer::Error::BroadcastError { shape_a: vec![2, 3], shape_b: vec![4], axis: 1 };
+    assert_eq!(err.to_string(), "shapes [2, 3] and [4] cannot be broadcast together on axis 1");
+    assert!(err.source().is_none());
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_error_source() {
+    use std::error::Error;
+
+    let err = buffer::Error::from(native::Error::OutOfMemory);
+    assert_eq!(err.to_string(), "native device error: out of host memory");
+    assert_eq!(err.source().unwrap().to_string(), "out of host memory");
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_slice_type_checks() {
+    let backend = native::Backend::default();
+    let mut mem = backend.device().alloc_memory(6).unwrap();
+
+    assert_eq!(mem.try_as_slice::<u16>().unwrap().len(), 3);
+    assert!(match mem.try_as_slice::<u32>() { Err(native::Error::InvalidSize) => true, _ => false });
+    assert!(match mem.try_as_mut_slice::<u64>() { Err(native::Error::InvalidSize) => true, _ => false });
+    assert!(match mem.try_as_slice::<()>() { Err(native::Error::InvalidSize) => true, _ => false });
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_memory_type_tag() {
+    let backend = native::Backend::default();
+    let dev = backend.device();
+    let buf: Buffer<f32> = Buffer::new(dev, 4).unwrap();
+    let lbuf = buf.try_lock().unwrap();
+
+    {
+      let mem = lbuf.native_memory(dev).unwrap();
+      assert!(match mem.try_as_slice::<u64>() { Err(native::Error::TypeMismatch) => true, _ => false });
+      assert!(match mem.try_as_slice::<u32>() { Err(native::Error::TypeMismatch) => true, _ => false });
+      assert_eq!(mem.try_as_slice::<f32>().unwrap().len(), 4);
+      assert_eq!(mem.try_as_slice::<u8>().unwrap().len(), 16);
+    }
+
+    drop(buf);
+    let lbuf = lbuf.reinterpret::<u32>().unwrap();
+    assert_eq!(lbuf.native_memory(dev).unwrap().try_as_slice::<u32>().unwrap().len(), 4);
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_size_overflow() {
+    let backend = native::Backend::default();
+    let overflows = |r: Result<Buffer<f64>, buffer::Error>| match r {
+      Err(buffer::Error::AllocationOverflow { size, element }) => size == usize::MAX && element == 8,
+      _ => false
+    };
+
+    assert!(overflows(Buffer::new(backend.device(), usize::MAX)));
+    assert!(overflows(Buffer::new_aligned(backend.device(), usize::MAX, 64)));
+
+    let r = Buffer::<f64>::new(backend.device(), 1).unwrap().lock().
+      and_then(|b| b.resize(usize::MAX, 0.0)).wait();
+    assert!(match r { Err(buffer::Error::AllocationOverflow { .. }) => true, _ => false });
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_alloc_failure() {
+    let backend = native::Backend::default();
+    let dev = backend.device();
+
+    // A valid layout no allocator can satisfy, so alloc returns null
+    let size = isize::MAX as usize - 63;
+    assert!(match dev.alloc_memory(size) { Err(native::Error::OutOfMemory) => true, _ => false });
+    assert!(match dev.alloc_memory_aligned(size, 64) { Err(native::Error::OutOfMemory) => true, _ => false });
+    assert_eq!(dev.allocated_bytes(), 0);
+
+    let err: buffer::Error = native::Error::OutOfMemory.into();
+    assert!(match err { buffer::Error::Native(native::Error::OutOfMemory) => true, _ => false });
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_copy_from() {
+    let backend = native::Backend::default();
+    let dev = backend.device();
+
+    let a = Buffer::from_vec_native(dev, vec![1.0f32, 2.0, 3.0]).unwrap();
+    let b = Buffer::<f32>::zeros_native(dev, 3).unwrap();
+    let rv = b.lock().join(a.lock()).
+      and_then(|(b, a)| b.copy_from(a, dev)).
+      and_then(|(b, _)| b.sync_to_vec()).wait().unwrap();
+    assert_eq!(rv, vec![1.0, 2.0, 3.0]);
+
+    let c = Buffer::<f32>::zeros_native(dev, 4).unwrap();
+    let r = c.lock().join(a.lock()).and_then(|(c, a)| c.copy_from(a, dev)).wait();
+    assert!(match r { Err(buffer::Error::SizeMismatch { expected: 4, actual: 3 }) => true, _ => false });
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_npy_round_trip() {
+    use std::env;
+    use std::fs;
+
+    let backend = native::Backend::default();
+    let dev = backend.device();
+    let path = env::temp_dir().join(format!("popcorn-test-{}.npy", std::process::id()));
+
+    let a = Buffer::from_vec_native(dev, vec![1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+    let a = a.lock().wait().unwrap();
+    io::save_npy(&path, &a, &[2, 3]).unwrap();
+    assert!(match io::save_npy(&path, &a, &[4]) { Err(io::Error::ShapeMismatch { .. }) => true, _ => false });
+
+    let bytes = fs::read(&path).unwrap();
+    assert_eq!(&bytes[..6], b"\x93NUMPY");
+    assert_eq!((bytes.len() - 6 * 4) % 64, 0);
+
+    let (b, shape) = io::load_npy::<f32, _>(&path, dev).unwrap();
+    assert_eq!(shape, vec![2, 3]);
+    assert_eq!(b.lock().and_then(|b| b.sync_to_vec()).wait().unwrap(), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+
+    match io::load_npy::<f64, _>(&path, dev) {
+      Err(io::Error::DtypeMismatch { expected, ref found }) => {
+        assert_eq!(expected, "<f8");
+        assert_eq!(found, "<f4");
+      },
+      _ => panic!("expected a dtype mismatch")
+    }
+
+    let c = Buffer::from_vec_native(dev, vec![-1i32, 7]).unwrap().lock().wait().unwrap();
+    io::save_npy(&path, &c, &[2]).unwrap();
+    let (c, shape) = io::load_npy::<i32, _>(&path, dev).unwrap();
+    assert_eq!(shape, vec![2]);
+    assert_eq!(c.lock().and_then(|c| c.sync_to_vec()).wait().unwrap(), vec![-1, 7]);
+
+    let c = c.lock().wait().unwrap();
+    assert!(match io::save_npy(&path, &c, &[usize::MAX, 2]) { Err(io::Error::ShapeMismatch { .. }) => true, _ => false });
+
+    // Hostile shapes, one overflowing and one larger than the data
+    for shape in &["(4611686018427387904, 4)", "(1000000000,)"] {
+      let dict = format!("{{'descr': '<i4', 'fortran_order': False, 'shape': {}, }}\n", shape);
+      let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+      bytes.extend_from_slice(&(dict.len() as u16).to_le_bytes());
+      bytes.extend_from_slice(dict.as_bytes());
+      bytes.extend_from_slice(&[0u8; 8]);
+      fs::write(&path, &bytes).unwrap();
+
+      assert!(match io::load_npy::<i32, _>(&path, dev) { Err(io::Error::InvalidHeader) => true, _ => false });
+    }
+
+    fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_full_and_ones() {
+    let backend = native::Backend::default();
+    let dev = backend.device();
+
+    let v = Buffer::full_native(dev, 3, 2.5f32).
+      and_then(|b| b.lock()).and_then(|b| b.sync_to_vec()).wait().unwrap();
+    assert_eq!(v, vec![2.5, 2.5, 2.5]);
+
+    let v = Buffer::<i32>::ones_native(dev, 4).
+      and_then(|b| b.lock()).and_then(|b| b.sync_to_vec()).wait().unwrap();
+    assert_eq!(v, vec![1, 1, 1, 1]);
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_arange() {
+    let backend = native::Backend::default();
+    let dev = backend.device();
+    let read = |b: Box<Future<Item=Buffer<f64>,Error=buffer::Error>>| {
+      b.and_then(|b| b.lock()).and_then(|b| b.sync_to_vec()).wait().unwrap()
+    };
+
+    assert_eq!(read(Buffer::arange_native(dev, 0.0, 1.0, 0.25)), vec![0.0, 0.25, 0.5, 0.75]);
+    assert_eq!(read(Buffer::arange_native(dev, 1.0, 0.0, -0.5)), vec![1.0, 0.5]);
+    assert!(read(Buffer::arange_native(dev, 1.0, 1.0, 0.5)).is_empty());
+    assert!(read(Buffer::arange_native(dev, 1.0, 0.0, 0.5)).is_empty());
+
+    let v = Buffer::arange_native(dev, 0i32, 7, 3).
+      and_then(|b| b.lock()).and_then(|b| b.sync_to_vec()).wait().unwrap();
+    assert_eq!(v, vec![0, 3, 6]);
+
+    let r = Buffer::arange_native(dev, 0i32, 7, 0).wait();
+    assert!(match r { Err(buffer::Error::InvalidStep) => true, _ => false });
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_linspace() {
+    let backend = native::Backend::default();
+    let dev = backend.device();
+    let read = |b: Box<Future<Item=Buffer<f32>,Error=buffer::Error>>| {
+      b.and_then(|b| b.lock()).and_then(|b| b.sync_to_vec()).wait().unwrap()
+    };
+
+    assert_eq!(read(Buffer::linspace_native(dev, 0.0, 1.0, 5)), vec![0.0, 0.25, 0.5, 0.75, 1.0]);
+    assert_eq!(read(Buffer::linspace_native(dev, 3.0, 9.0, 1)), vec![3.0]);
+    assert!(read(Buffer::linspace_native(dev, 0.0, 1.0, 0)).is_empty());
+
+    let v = Buffer::linspace_native(dev, 0i32, 10, 3).
+      and_then(|b| b.lock()).and_then(|b| b.sync_to_vec()).wait().unwrap();
+    assert_eq!(v, vec![0, 5, 10]);
+
+    let v = Buffer::linspace_native(dev, 0i32, 1_000_000, 10_000).
+      and_then(|b| b.lock()).and_then(|b| b.sync_to_vec()).wait().unwrap();
+    assert_eq!(v.len(), 10_000);
+    assert_eq!((v[0], v[1], v[5000], v[9999]), (0, 100, 500_050, 1_000_000));
+
+    let v = Buffer::linspace_native(dev, i32::MIN, i32::MAX, 3).
+      and_then(|b| b.lock()).and_then(|b| b.sync_to_vec()).wait().unwrap();
+    assert_eq!(v, vec![i32::MIN, -1, i32::MAX]);
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_random_seeded() {
+    let backend = native::Backend::default();
+    let dev = backend.device();
+    let read = |b: Box<Future<Item=Buffer<f32>,Error=buffer::Error>>| {
+      b.and_then(|b| b.lock()).and_then(|b| b.sync_to_vec()).wait().unwrap()
+    };
+
+    let a = read(Buffer::random_uniform_native(dev, 100, -1.0, 1.0, 42));
+    let b = read(Buffer::random_uniform_native(dev, 100, -1.0, 1.0, 42));
+    let c = read(Buffer::random_uniform_native(dev, 100, -1.0, 1.0, 43));
+    assert_eq!(a, b);
+    assert!(a != c);
+    assert!(a.iter().all(|v| *v >= -1.0 && *v < 1.0));
+
+    let a = read(Buffer::random_normal_native(dev, 1001, 5.0, 2.0, 7));
+    let b = read(Buffer::random_normal_native(dev, 1001, 5.0, 2.0, 7));
+    let c = read(Buffer::random_normal_native(dev, 1001, 5.0, 2.0, 8));
+    assert_eq!(a.len(), 1001);
+    assert_eq!(a, b);
+    assert!(a != c);
+
+    let mean = a.iter().sum::<f32>() / a.len() as f32;
+    assert!((mean - 5.0).abs() < 0.5);
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_concat_and_chunks() {
+    let backend = native::Backend::default();
+    let dev = backend.device();
+
+    let a = Buffer::from_vec_native(dev, vec![1, 2, 3]).unwrap().try_lock().unwrap();
+    let b = Buffer::from_vec_native(dev, vec![4, 5]).unwrap().try_lock().unwrap();
+    let (mut inputs, c) = Buffer::concat_native(dev, vec![a, b]).wait().unwrap();
+    assert_eq!(inputs.remove(0).sync_to_vec().wait().unwrap(), vec![1, 2, 3]);
+    let c = c.try_lock().unwrap();
+
+    let (c, chunks) = c.chunks(2).wait().unwrap();
+    assert_eq!(c.sync_to_vec().wait().unwrap(), vec![1, 2, 3, 4, 5]);
+    let vs: Vec<Vec<i32>> = chunks.iter().map(|c| c.lock().and_then(|c| c.sync_to_vec()).wait().unwrap()).collect();
+    assert_eq!(vs, vec![vec![1, 2], vec![3, 4], vec![5]]);
+
+    let joined = chunks.iter().map(|c| c.try_lock().unwrap()).collect::<Vec<_>>();
+    let d = Buffer::concat_native(dev, joined).and_then(|(_, d)| d.lock()).and_then(|d| d.sync_to_vec()).wait().unwrap();
+    assert_eq!(d, vec![1, 2, 3, 4, 5]);
+
+    let (_, empty) = Buffer::<i32>::concat_native(dev, vec![]).wait().unwrap();
+    assert_eq!(empty.lock().and_then(|e| e.sync_to_vec()).wait().unwrap(), Vec::<i32>::new());
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_buffer_id() {
+    let backend = native::Backend::default();
+    let dev = backend.device();
+
+    let a = Buffer::from_vec_native(dev, vec![1.0f32, 2.0]).unwrap();
+    let b = a.clone();
+    assert_eq!(a.id(), b.id());
+
+    let (_, c) = a.lock().and_then(|a| a.deep_copy(dev)).wait().unwrap();
+    assert!(c.id() != a.id());
+    assert!(c.id() > a.id());
+
+    let d = Buffer::<f32>::new(dev, 2).unwrap();
+    assert!(d.id() != a.id() && d.id() != c.id());
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_spawn() {
+    let backend = native::Backend::default();
+    let dev = backend.device().clone();
+    let buf = Buffer::from_vec_native(&dev, vec![1.0f32, 2.0, 3.0]).unwrap();
+
+    let worker_dev = dev.clone();
+    let sum = buf.lock().and_then(move |b| dev.spawn(move || -> Result<f32, buffer::Error> {
+      let s: &[f32] = try!(try!(b.native_memory(&worker_dev)).try_as_slice());
+      Ok(s.iter().sum::<f32>())
+    })).wait().unwrap();
+    assert_eq!(sum, 6.0);
+
+    let err = backend.device().spawn(|| -> Result<(), &'static str> { Err("failed") }).wait();
+    assert_eq!(err, Err("failed"));
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_synchronize() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use std::time::Duration;
+
+    let backend = native::Backend::with_threads(2).unwrap();
+    let done = Arc::new(AtomicUsize::new(0));
+    for _ in 0..4 {
+      let done = done.clone();
+      backend.device().pool().spawn_fn(move || -> Result<(), ()> {
+        thread::sleep(Duration::from_millis(50));
+        done.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+      }).forget();
+    }
+
+    backend.synchronize().wait().unwrap();
+    assert_eq!(done.load(Ordering::SeqCst), 4);
+
+    // Nothing queued
+    backend.synchronize().wait().unwrap();
+  }
+
+  #[test]
+  #[cfg(all(feature = "native", unix))]
+  fn test_native_pinned() {
+    let backend = native::Backend::default();
+    let dev = backend.device();
+
+    let mem = dev.alloc_pinned(64).unwrap();
+    assert!(mem.is_pinned());
+    assert!(!mem.clone().is_pinned());
+
+    let buf: Buffer<f32> = Buffer::new_pinned(dev, 3).unwrap();
+    assert!(buf.try_lock().unwrap().native_memory(dev).unwrap().is_pinned());
+
+    let v = buf.lock().and_then(|b| b.sync_from_vec(vec![1.0, 2.0, 3.0])).
+      and_then(|b| b.sync_to_vec()).wait().unwrap();
+    assert_eq!(v, vec![1.0, 2.0, 3.0]);
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_buffer_pool() {
+    let backend = native::Backend::default();
+    let dev = backend.device();
+    let pool = BufferPool::new(1024);
+
+    let ptr = |buf: &Buffer<f32>| buf.try_lock().unwrap().native_memory(dev).unwrap().as_ptr() as usize;
+
+    let a = pool.acquire::<f32>(dev, 16).unwrap();
+    a.try_lock().unwrap().set(dev, 0, 5.0).unwrap();
+    let p = ptr(&a);
+    drop(a);
+    assert_eq!(pool.cached_bytes(), 64);
+
+    // Same number of bytes, so the memory is handed back as is
+    let b = pool.acquire::<i32>(dev, 16).unwrap();
+    assert_eq!(pool.cached_bytes(), 0);
+    assert_eq!(b.try_lock().unwrap().native_memory(dev).unwrap().as_ptr() as usize, p);
+    drop(b);
+
+    let c = pool.acquire_zeroed::<f32>(dev, 16).unwrap();
+    assert_eq!(ptr(&c), p);
+    assert_eq!(c.try_lock().unwrap().get(dev, 0).unwrap(), 0.0);
+
+    // A clone outlives the pooled handle, so nothing is returned
+    let clone = (*c).clone();
+    drop(c);
+    assert_eq!(pool.cached_bytes(), 0);
+    drop(clone);
+
+    // Over capacity
+    drop(pool.acquire::<f32>(dev, 512).unwrap());
+    assert_eq!(pool.cached_bytes(), 0);
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_map_inplace() {
+    let backend = native::Backend::default();
+    let buf = Buffer::from_vec_native(backend.device(), vec![1.0f32, -2.0, 3.0]).unwrap();
+
+    let v = buf.lock().and_then(|b| b.map_inplace(|x| x * x)).
+      and_then(|b| b.sync_to_vec()).wait().unwrap();
+    assert_eq!(v, vec![1.0, 4.0, 9.0]);
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_sync_from_vec_length() {
+    let backend = native::Backend::default();
+    let buf = Buffer::from_vec_native(backend.device(), vec![1.0f32, 2.0]).unwrap();
+
+    match buf.lock().and_then(|b| b.sync_from_vec(vec![1.0, 2.0, 3.0])).wait() {
+      Err(buffer::Error::SizeMismatch { expected, actual }) => assert_eq!((expected, actual), (2, 3)),
+      r => panic!("expected a size mismatch, got {:?}", r.map(|_| ()))
+    }
+
+    // The buffer is left untouched
+    let v = buf.lock().and_then(|b| b.sync_to_vec()).wait().unwrap();
+    assert_eq!(v, vec![1.0, 2.0]);
+  }
+
+  #[test]
+  #[cfg(all(feature = "native", feature = "ndarray"))]
+  fn test_native_ndarray_round_trip() {
+    use ndarray::{arr2, IxDyn};
+
+    let backend = native::Backend::default();
+    let a = arr2(&[[1.0f32, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+
+    let (buf, shape) = Buffer::from_ndarray(backend.device(), &a.view()).unwrap();
+    assert_eq!(shape, vec![2, 3]);
+    let b = buf.lock().and_then(|b| b.to_ndarray(shape)).wait().unwrap();
+    assert_eq!(b, a.clone().into_dyn());
+
+    // Transposed views are not contiguous, and are copied in standard order
+    let (buf, shape) = Buffer::from_ndarray(backend.device(), &a.t()).unwrap();
+    let t = buf.lock().and_then(|b| b.to_ndarray(shape)).wait().unwrap();
+    assert_eq!(t.shape(), &[3, 2]);
+    assert_eq!(t[IxDyn(&[0, 1])], 4.0);
+
+    let wrong = buf.lock().and_then(|b| b.to_ndarray(vec![4, 2])).wait();
+    assert!(wrong.is_err());
+  }
+
+  #[test]
+  #[cfg(all(feature = "native", feature = "bytemuck"))]
+  fn test_native_pod_cast() {
+    let backend = native::Backend::default();
+    let dev = backend.device();
+
+    let bytes: Vec<u8> = [1.5f32, -2.0].iter().flat_map(|v| v.to_ne_bytes().to_vec()).collect();
+    let buf = Buffer::from_pod_slice(dev, &bytes).unwrap();
+    let lbuf = buf.try_lock().unwrap();
+    let mem = lbuf.native_memory(dev).unwrap();
+
+    assert_eq!(mem.try_as_pod_slice::<f32>().unwrap(), &[1.5, -2.0]);
+    assert_eq!(mem.try_as_pod_slice::<u8>().unwrap(), &bytes[..]);
+    assert!(mem.try_as_pod_slice::<[u8; 3]>().is_err());
+
+    // The unchecked path still insists on the allocated element type
+    assert!(mem.try_as_slice::<f32>().is_err());
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_unlock() {
+    let backend = native::Backend::default();
+    let buf = Buffer::from_vec_native(backend.device(), vec![1.0f32, 2.0]).unwrap();
+
+    let unlocked = buf.lock().and_then(|b| b.fill(3.0)).map(|b| b.unlock()).wait().unwrap();
+    assert!(Buffer::ptr_eq(&unlocked, &buf));
+
+    let v = unlocked.lock().and_then(|b| b.sync_to_vec()).wait().unwrap();
+    assert_eq!(v, vec![3.0, 3.0]);
+    assert!(buf.try_lock().is_ok());
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_devices() {
+    let backend = native::Backend::default();
+    let dev = backend.device();
+    let buf = Buffer::<f32>::new(dev, 4).unwrap();
+
+    let lbuf = buf.try_lock().unwrap();
+    assert_eq!(lbuf.devices(), vec![BufferDevice::Native(dev.clone())]);
+    assert_eq!(lbuf.latest_device(), &BufferDevice::Native(dev.clone()));
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_evict() {
+    let framework = native::Framework::new();
+    let dev_a = framework.default_device();
+    let dev_b = framework.default_device();
+
+    let buf: Buffer<f32> = Buffer::from_vec_native(&dev_a, vec![1.0, 2.0]).unwrap();
+    let mut lbuf = buf.lock().and_then(|b| b.sync(&dev_b)).wait().unwrap();
+    assert_eq!(dev_a.allocated_bytes(), 8);
+
+    match lbuf.evict(&BufferDevice::Native(dev_b.clone())) {
+      Err(buffer::Error::LatestCopy) => (),
+      r => panic!("expected the latest copy to be kept, got {:?}", r)
+    }
+
+    lbuf.evict(&BufferDevice::Native(dev_a.clone())).unwrap();
+    assert_eq!(dev_a.allocated_bytes(), 0);
+    assert!(lbuf.evict(&BufferDevice::Native(dev_a.clone())).is_err());
+    assert_eq!(lbuf.devices(), vec![BufferDevice::Native(dev_b.clone())]);
+    assert_eq!(lbuf.sync_to_vec().wait().unwrap(), vec![1.0, 2.0]);
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_flush() {
+    let framework = native::Framework::new();
+    let dev_a = framework.default_device();
+    let dev_b = framework.default_device();
+
+    let buf: Buffer<f32> = Buffer::from_vec_native(&dev_a, vec![1.0, 2.0]).unwrap();
+    let mut lbuf = buf.lock().and_then(|b| b.sync(&dev_b)).wait().unwrap();
+    lbuf.set(&dev_a, 0, 5.0).unwrap();
+    assert!(lbuf.native_memory(&dev_b).is_err());
+
+    let lbuf = lbuf.flush(&[&dev_a, &dev_b]).wait().unwrap();
+    assert_eq!(lbuf.latest_device(), &BufferDevice::Native(dev_a.clone()));
+    assert_eq!(lbuf.get(&dev_b, 0).unwrap(), 5.0);
+    assert_eq!(lbuf.get(&dev_b, 1).unwrap(), 2.0);
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_content_eq_and_debug() {
+    let backend = native::Backend::default();
+    let dev = backend.device();
+
+    let a = Buffer::from_vec_native(dev, vec![1i32, 2, 3]).unwrap().try_lock().unwrap();
+    let b = Buffer::from_vec_native(dev, vec![1i32, 2, 3]).unwrap().try_lock().unwrap();
+    let c = Buffer::from_vec_native(dev, vec![1i32, 2, 4]).unwrap().try_lock().unwrap();
+    let d = Buffer::from_vec_native(dev, vec![1i32, 2]).unwrap().try_lock().unwrap();
+    let (a, _, eq) = a.content_eq(b, dev).wait().unwrap();
+    assert!(eq);
+    let (a, _, eq) = a.content_eq(c, dev).wait().unwrap();
+    assert!(!eq);
+    let (a, _, eq) = a.content_eq(d, dev).wait().unwrap();
+    assert!(!eq);
+
+    // The latest copy of `e` lives on another device
+    let other = native::Backend::with_threads(1).unwrap();
+    let e = Buffer::from_vec_native(other.device(), vec![1i32, 2, 3]).unwrap().try_lock().unwrap();
+    let (a, _, eq) = a.content_eq(e, dev).wait().unwrap();
+    assert!(eq);
+
+    assert!(format!("{:?}", a).contains("data: [1, 2, 3]"));
+    let long = Buffer::from_vec_native(dev, (0..20).collect::<Vec<i32>>()).unwrap().try_lock().unwrap();
+    assert!(format!("{:?}", long).contains("data: [0, 1, 2, 3, ..., 16, 17, 18, 19]"));
+  }
+
+  #[test]
+  #[cfg(all(feature = "native", feature = "half"))]
+  fn test_native_f16_round_trip() {
+    let backend = native::Backend::default();
+    let dev = backend.device();
+
+    let vs = vec![0.1f32, -2.5, 1000.0, 3.25];
+    let src = Buffer::from_vec_native(dev, vs.clone()).unwrap().try_lock().unwrap();
+    let half = LockedBuffer::<f16>::from_f32(src, dev).wait().unwrap();
+    let back = half.lock().and_then(|h| h.to_f32_buffer(dev)).
+      and_then(|b| b.lock().and_then(|b| b.sync_to_vec())).wait().unwrap();
+
+    for (v, b) in vs.iter().zip(back.iter()) {
+      assert!((v - b).abs() <= v.abs() * 1e-3, "{} became {}", v, b);
+    }
+
+    let one = Buffer::<f16>::ones_native(dev, 2).and_then(|b| b.lock()).
+      and_then(|b| b.to_f32_buffer(dev)).and_then(|b| b.lock().and_then(|b| b.sync_to_vec())).wait().unwrap();
+    assert_eq!(one, vec![1.0, 1.0]);
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_supported_ops() {
+    let framework = native::Framework::new();
+    assert_eq!(native::Framework::name(), "native");
+    assert!(framework.supported_ops().contains(&OpKind::Dot));
+    assert!(framework.supported_ops().contains(&OpKind::Softmax));
+  }
+
+  #[test]
+  fn test_error_into_io() {
+    use std::io;
+
+    let err: io::Error = buffer::Error::InvalidDevice.into();
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    assert_eq!(err.to_string(), "buffer has no memory on the device");
+
+    let err: io::Error = buffer::Error::InvalidBroadcast.into();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    assert!(err.get_ref().unwrap().source().is_none());
+
+    fn read() -> io::Result<()> {
+      try!(Err(buffer::Error::Cancelled));
+      Ok(())
+    }
+    assert_eq!(read().unwrap_err().kind(), io::ErrorKind::Interrupted);
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_join_buffers() {
+    let backend = native::Backend::default();
+    let dev = backend.device();
+
+    let futs = (0..3).map(|i| {
+      let buf = Buffer::<f32>::with_capacity_native(dev, 4).unwrap().try_lock().unwrap();
+      buf.fill(i as f32)
+    }).collect();
+    let bufs = join_buffers(futs).wait().unwrap();
+    let vs: Vec<Vec<f32>> = bufs.into_iter().map(|b| b.sync_to_vec().wait().unwrap()).collect();
+    assert_eq!(vs, vec![vec![0.0; 4], vec![1.0; 4], vec![2.0; 4]]);
+
+    // The first error fails the whole join
+    let buf = Buffer::<f32>::with_capacity_native(dev, 4).unwrap().try_lock().unwrap();
+    let futs: Vec<Box<Future<Item=LockedBuffer<f32>,Error=buffer::Error>>> = vec![
+      buf.fill(1.0),
+      Box::new(futures::future::err(buffer::Error::InvalidDevice))
+    ];
+    match join_buffers(futs).wait() {
+      Err(buffer::Error::InvalidDevice) => (),
+      _ => panic!("expected the join to fail")
+    }
+  }
+
+  #[test]
+  #[cfg(feature = "native")]
+  fn test_native_synchronize_custom_pool() {
+    let mut builder = futures_cpupool::Builder::new();
+    builder.pool_size(1);
+    let dev = native::Device::new(native::Hardware::new(), builder);
+    assert_eq!(dev.threads(), num_cpus::get());
+    dev.synchronize().wait().unwrap();
+
+    let builder = futures_cpupool::Builder::new();
+    let dev = native::Device::with_pool_size(native::Hardware::new(), builder, 3);
+    assert_eq!(dev.threads(), 3);
+    dev.spawn(|| -> Result<(), native::Error> { Ok(()) }).join(dev.synchronize()).wait().unwrap();
+  }
+
+  #[test]
+  #[cfg(all(feature = "native", unix))]
+  fn test_native_pinned_whole_pages() {
+    let backend = native::Backend::default();
+    let dev = backend.device();
+    let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+
+    let a = dev.alloc_pinned(64).unwrap();
+    let b = dev.alloc_pinned(64).unwrap();
+    assert_eq!(a.len(), 64);
+    assert_eq!(b.len(), 64);
+
+    // Each starts a page of its own, so unpinning one leaves the
+    // other locked
+    let (pa, pb) = (a.as_ptr() as usize, b.as_ptr() as usize);
+    assert_eq!(pa % page, 0);
+    assert_eq!(pb % page, 0);
+    assert!(pa.max(pb) - pa.min(pb) >= page);
+  }
 }
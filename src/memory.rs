@@ -1 +1,9 @@
-pub trait Memory { }
+pub trait Memory {
+  fn byte_len(&self) -> usize;
+
+  /// Length of the allocation. Memory is untyped, so this is counted
+  /// in bytes just like `byte_len`.
+  fn len(&self) -> usize { self.byte_len() }
+
+  fn is_empty(&self) -> bool { self.len() == 0 }
+}
@@ -0,0 +1,100 @@
+//! Numeric identities for the element types buffers are built from.
+
+use std::ops::{Add, Sub, Mul, Div};
+#[cfg(feature = "half")]
+use half;
+
+pub trait Zero {
+  fn zero() -> Self;
+}
+
+pub trait One {
+  fn one() -> Self;
+}
+
+macro_rules! impl_identities {
+  ($zero:expr, $one:expr, $($t:ty),*) => {
+    $(
+      impl Zero for $t {
+        fn zero() -> $t { $zero }
+      }
+
+      impl One for $t {
+        fn one() -> $t { $one }
+      }
+    )*
+  }
+}
+
+impl_identities!(0, 1, u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+impl_identities!(0.0, 1.0, f32, f64);
+
+#[cfg(feature = "half")]
+impl Zero for half::f16 {
+  fn zero() -> half::f16 { half::f16::from_f32(0.0) }
+}
+
+#[cfg(feature = "half")]
+impl One for half::f16 {
+  fn one() -> half::f16 { half::f16::from_f32(1.0) }
+}
+
+/// Element types with the arithmetic used by sequences and by the
+/// elementwise and reduction operations. New element types only need
+/// to implement this, and `Zero` and `One`.
+pub trait Numeric: Copy + PartialOrd + Zero + One +
+  Add<Output=Self> + Sub<Output=Self> + Mul<Output=Self> + Div<Output=Self> {
+  fn from_usize(n: usize) -> Self;
+  fn from_f64(v: f64) -> Self;
+  fn to_f64(self) -> f64;
+
+  /// `self / rhs`, or `None` where that would panic. Floats never
+  /// panic and follow IEEE 754, dividing by zero gives an infinity or
+  /// NaN.
+  fn checked_div(self, rhs: Self) -> Option<Self>;
+}
+
+macro_rules! impl_numeric {
+  (int: $($t:ty),*) => {
+    $(
+      impl Numeric for $t {
+        fn from_usize(n: usize) -> $t { n as $t }
+        fn from_f64(v: f64) -> $t { v as $t }
+        fn to_f64(self) -> f64 { self as f64 }
+        fn checked_div(self, rhs: $t) -> Option<$t> { <$t>::checked_div(self, rhs) }
+      }
+    )*
+  };
+  (float: $($t:ty),*) => {
+    $(
+      impl Numeric for $t {
+        fn from_usize(n: usize) -> $t { n as $t }
+        fn from_f64(v: f64) -> $t { v as $t }
+        fn to_f64(self) -> f64 { self as f64 }
+        fn checked_div(self, rhs: $t) -> Option<$t> { Some(self / rhs) }
+      }
+    )*
+  }
+}
+
+impl_numeric!(int: i32, i64);
+impl_numeric!(float: f32, f64);
+
+/// Floating point element types.
+pub trait Float: Numeric {
+  fn exp(self) -> Self;
+  fn tanh(self) -> Self;
+}
+
+macro_rules! impl_float {
+  ($($t:ty),*) => {
+    $(
+      impl Float for $t {
+        fn exp(self) -> $t { <$t>::exp(self) }
+        fn tanh(self) -> $t { <$t>::tanh(self) }
+      }
+    )*
+  }
+}
+
+impl_float!(f32, f64);
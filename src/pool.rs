@@ -0,0 +1,143 @@
+//! Reuse of native allocations between buffers.
+//!
+//! Buffers acquired from a `BufferPool` hand their memory back to the
+//! pool when dropped, and the next buffer of the same byte size on the
+//! same device takes it over instead of allocating again.
+
+use std::collections::HashMap;
+use std::mem;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex, MutexGuard};
+use buffer::{Buffer, RawBuffer, Error};
+use frameworks::native;
+
+#[derive(Clone)]
+pub struct BufferPool {
+  inner: Arc<Mutex<Inner>>
+}
+
+struct Inner {
+  // Unused memory, keyed by device and byte size
+  free: HashMap<(native::Device, usize), Vec<native::Memory>>,
+  cached: usize,
+  capacity: usize
+}
+
+/// A buffer whose memory goes back to its pool once this is the last
+/// handle to it and it is dropped.
+pub struct PooledBuffer<T: Send + Copy + Sized + 'static> {
+  buffer: Option<Buffer<T>>,
+  dev: native::Device,
+  pool: BufferPool
+}
+
+impl BufferPool {
+  /// A pool keeping at most `capacity` bytes of unused memory around,
+  /// anything beyond is freed.
+  pub fn new(capacity: usize) -> BufferPool {
+    BufferPool {
+      inner: Arc::new(Mutex::new(Inner {
+        free: HashMap::new(),
+        cached: 0,
+        capacity: capacity
+      }))
+    }
+  }
+
+  /// A buffer of `size` elements on `dev`. Reused memory still holds
+  /// whatever was last written to it.
+  pub fn acquire<T: Send + Copy + Sized + 'static>(&self,
+                                                   dev: &native::Device,
+                                                   size: usize) -> Result<PooledBuffer<T>, Error> {
+    let bytes = try!(size.checked_mul(mem::size_of::<T>()).ok_or(Error::AllocationOverflow {
+      size: size,
+      element: mem::size_of::<T>()
+    }));
+
+    let raw = match self.take(dev, bytes) {
+      Some(mem) => try!(RawBuffer::from_memory_native(dev, size, mem)),
+      None => try!(RawBuffer::new(dev, size))
+    };
+
+    Ok(PooledBuffer {
+      buffer: Some(raw.into()),
+      dev: dev.clone(),
+      pool: self.clone()
+    })
+  }
+
+  /// Like `acquire`, but reused memory is zeroed first.
+  pub fn acquire_zeroed<T: Send + Copy + Sized + 'static>(&self,
+                                                          dev: &native::Device,
+                                                          size: usize) -> Result<PooledBuffer<T>, Error> {
+    let buf = try!(self.acquire::<T>(dev, size));
+    try!(try!(buf.try_lock()).native_memory_mut(dev)).zero();
+    Ok(buf)
+  }
+
+  /// Number of bytes of unused memory held by the pool.
+  pub fn cached_bytes(&self) -> usize {
+    self.inner().cached
+  }
+
+  /// Free all unused memory.
+  pub fn clear(&self) {
+    let mut inner = self.inner();
+    inner.free.clear();
+    inner.cached = 0;
+  }
+
+  fn take(&self, dev: &native::Device, bytes: usize) -> Option<native::Memory> {
+    let mut inner = self.inner();
+    let mem = inner.free.get_mut(&(dev.clone(), bytes)).and_then(|mems| mems.pop());
+    if mem.is_some() {
+      inner.cached -= bytes;
+    }
+    mem
+  }
+
+  fn release(&self, dev: &native::Device, mem: native::Memory) {
+    let mut inner = self.inner();
+    if inner.cached + mem.len() > inner.capacity {
+      return
+    }
+
+    inner.cached += mem.len();
+    inner.free.entry((dev.clone(), mem.len())).or_insert_with(Vec::new).push(mem);
+  }
+
+  // Nothing can be left half updated by a panic, so a poisoned lock
+  // is still usable
+  fn inner(&self) -> MutexGuard<Inner> {
+    self.inner.lock().unwrap_or_else(|e| e.into_inner())
+  }
+}
+
+impl<T: Send + Copy + Sized + 'static> PooledBuffer<T> {
+  /// Detach the buffer from the pool, its memory is then freed as
+  /// usual.
+  pub fn into_buffer(mut self) -> Buffer<T> {
+    self.buffer.take().unwrap()
+  }
+}
+
+impl<T: Send + Copy + Sized + 'static> Deref for PooledBuffer<T> {
+  type Target = Buffer<T>;
+
+  fn deref(&self) -> &Buffer<T> { self.buffer.as_ref().unwrap() }
+}
+
+impl<T: Send + Copy + Sized + 'static> Drop for PooledBuffer<T> {
+  fn drop(&mut self) {
+    // Clones of the buffer keep its memory alive, it is then freed
+    // with the last of them instead
+    let raw = match self.buffer.take().map(|b| b.into_raw()) {
+      Some(Ok(raw)) => raw,
+      _ => return
+    };
+
+    if let Some(mem) = raw.into_native_memory(&self.dev) {
+      self.pool.release(&self.dev, mem);
+    }
+  }
+}
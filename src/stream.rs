@@ -0,0 +1,44 @@
+//! Running operations over streams of buffers.
+//!
+//! `map_op` feeds every item of a stream through an operation and
+//! yields the results in input order, keeping a bounded number of the
+//! operations in flight so that a long stream does not queue all of its
+//! work on the device pool at once.
+
+use std::cmp;
+use futures::{Stream, IntoFuture, Poll};
+use futures::stream::{Buffered, Map};
+
+/// `MapOp` is returned by `map_op`, yielding the result of the
+/// operation for each input item.
+#[must_use = "streams do nothing unless polled"]
+pub struct MapOp<S, F, U>
+  where S: Stream,
+        F: FnMut(S::Item) -> U,
+        U: IntoFuture<Error=S::Error> {
+  inner: Buffered<Map<S, F>>
+}
+
+/// Run `op` on every item of `stream`, with at most `limit` of the
+/// resulting futures pending at once. A `limit` of zero is treated as
+/// one.
+pub fn map_op<S, F, U>(stream: S, limit: usize, op: F) -> MapOp<S, F, U>
+  where S: Stream,
+        F: FnMut(S::Item) -> U,
+        U: IntoFuture<Error=S::Error> {
+  MapOp {
+    inner: stream.map(op).buffered(cmp::max(limit, 1))
+  }
+}
+
+impl<S, F, U> Stream for MapOp<S, F, U>
+  where S: Stream,
+        F: FnMut(S::Item) -> U,
+        U: IntoFuture<Error=S::Error> {
+  type Item = U::Item;
+  type Error = S::Error;
+
+  fn poll(&mut self) -> Poll<Option<U::Item>, S::Error> {
+    self.inner.poll()
+  }
+}
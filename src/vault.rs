@@ -14,9 +14,15 @@
 //! for the entire sequence of futures required to execute an operation.
 
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::collections::VecDeque;
 use std::cell::UnsafeCell;
 use std::ops::{Drop, Deref, DerefMut};
 use std::clone::Clone;
+use std::fmt;
+use std::thread;
+use std::hint;
+use std::time::{Duration, Instant};
 use futures::{Future, Poll, Async};
 use futures::task::{self, Task};
 use spin;
@@ -44,13 +50,39 @@ pub struct Vault<T> {
 /// by another owner, then the current thread will park a task which will be
 /// unparked at a later time when the lock has been released. Multiple threads
 /// can be waiting to acquire a lock at once.
+///
+/// Shared readers are counted in `readers`. A `Vault` is free for a writer
+/// only when it is neither locked nor has any active readers. New readers
+/// are held back while a writer is waiting on active readers so that
+/// writers are not starved.
 #[derive(Debug)]
 pub struct InnerLock {
   /// Whether or not this vault has been acquired
   locked: bool,
 
-  /// A queue of tasks that are waiting to acquire this vault
-  waiting: Vec<Task>,
+  /// Number of shared read guards currently alive
+  readers: usize,
+
+  /// A FIFO queue of tasks that are waiting to acquire this vault,
+  /// keyed by the id of the `VaultAcquire` that parked them. A woken
+  /// waiter keeps its place at the front until it has acquired
+  waiting: VecDeque<(usize, Task)>,
+
+  /// The id handed to the next `VaultAcquire` or `VaultRead` that
+  /// needs to park
+  next_waiter: usize,
+
+  /// Tasks that are waiting to acquire shared read access, keyed like
+  /// `waiting`
+  waiting_readers: Vec<(usize, Task)>,
+
+  /// Whether an owner panicked while holding the lock
+  poisoned: bool,
+
+  /// Number of `Vault` handles and lock guards alive. Once this drops
+  /// to zero the vault is closed and pending lock futures resolve to
+  /// an error
+  handles: usize,
 }
 
 /// The `Inner` structure holds a reference to a spin mutex which is used
@@ -62,6 +94,12 @@ pub struct InnerLock {
 /// making a spin lock much more performant than other locking structures.
 #[derive(Debug)]
 pub struct Inner<T> {
+  /// Unique for the lifetime of the process, shared by every handle
+  id: usize,
+
+  /// How to wait for `lock` while someone else holds it
+  policy: LockPolicy,
+
   /// A spin lock that guards access to the inner lock data
   lock: spin::Mutex<InnerLock>,
 
@@ -69,6 +107,21 @@ pub struct Inner<T> {
   data: UnsafeCell<T>
 }
 
+/// `LockPolicy` decides how a thread waits for the spin lock guarding
+/// the lock state of a `Vault` while another thread holds it. The lock
+/// is taken right away when it is free, whatever the policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockPolicy {
+  /// Spin until the lock is free, best when it is only held briefly
+  Spin,
+
+  /// Spin at most `spins` times, then yield the thread between
+  /// attempts, which wastes fewer cycles when threads outnumber cores
+  Backoff { spins: u32 }
+}
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
 // We can send our Vaults across threads
 unsafe impl<T: Send> Send for Inner<T> {}
 unsafe impl<T: Send> Sync for Inner<T> {}
@@ -79,7 +132,10 @@ unsafe impl<T: Send> Sync for Inner<T> {}
 /// access the synchronized data.
 #[derive(Debug)]
 pub struct VaultAcquire<T> {
-  inner: Arc<Inner<T>>
+  inner: Arc<Inner<T>>,
+
+  /// Our id in the waiting queue, once we have parked
+  waiter: Option<usize>
 }
 
 /// `VaultAcquired` is the structure that let's us actually access the
@@ -91,14 +147,82 @@ pub struct VaultAcquired<T> {
   inner: Arc<Inner<T>>
 }
 
+/// `MappedGuard` is created by `VaultAcquired::map`. It derefs to a
+/// projection of the locked data, such as a single field, while still
+/// holding the original lock. The lock is released when it is dropped.
+#[derive(Debug)]
+pub struct MappedGuard<T, U> {
+  /// Held only to keep the `Vault` locked
+  _acquired: VaultAcquired<T>,
+  data: *mut U
+}
+
+// The projected data is only reachable through the lock we hold
+unsafe impl<T: Send, U: Send> Send for MappedGuard<T, U> {}
+unsafe impl<T: Sync, U: Sync> Sync for MappedGuard<T, U> {}
+
+/// `PoisonError` is returned when a `Vault` is acquired after a previous
+/// owner panicked while holding it. The data may be inconsistent, but
+/// the guard can still be recovered with `into_inner`, just like with
+/// an `std::sync::Mutex`.
+pub struct PoisonError<G> {
+  guard: G
+}
+
+/// `LockError` describes why a `Vault` could not be acquired.
+pub enum LockError<G> {
+  /// A previous owner panicked while holding the lock
+  Poisoned(PoisonError<G>),
+
+  /// The lock is currently held by someone else
+  WouldBlock,
+
+  /// The lock could not be acquired within the requested duration
+  TimedOut,
+
+  /// Every `Vault` handle and guard was dropped while waiting for the
+  /// lock
+  Closed
+}
+
+/// `VaultRead` is returned by calling `lock_shared` on a `Vault`. Like
+/// `VaultAcquire`, it is a future, but it resolves to a `VaultReadGuard`
+/// which only grants read access to the data. Any number of read guards
+/// may be alive at once, but never alongside a `VaultAcquired`.
+#[derive(Debug)]
+pub struct VaultRead<T> {
+  inner: Arc<Inner<T>>,
+
+  /// Our id in the waiting readers, once we have parked
+  waiter: Option<usize>
+}
+
+/// `VaultReadGuard` gives shared, read-only access to the data of a
+/// `Vault`. Writers are excluded until every read guard has been dropped.
+#[derive(Debug)]
+pub struct VaultReadGuard<T> {
+  inner: Arc<Inner<T>>
+}
+
 impl<T> Clone for Vault<T> {
   fn clone(&self) -> Self {
+    self.inner.lock_state().handles += 1;
+
     Vault {
       inner: self.inner.clone()
     }
   }
 }
 
+impl<T> Drop for Vault<T> {
+  /// Close the `Vault` when the last handle is dropped and no guard is
+  /// left, waking every parked task so that its pending lock future can
+  /// resolve to an error instead of hanging forever.
+  fn drop(&mut self) {
+    self.inner.lock_state().release_handle();
+  }
+}
+
 impl<T> Vault<T> {
   /// Create a new `Vault` containing some data.
   ///
@@ -119,10 +243,32 @@ impl<T> Vault<T> {
   /// # assert_eq!(value, 34.5);
   /// ```
   pub fn new(t: T) -> Vault<T> {
+    Self::new_with_policy(t, LockPolicy::default())
+  }
+
+  /// Create a new `Vault` whose lock state is waited for according to
+  /// `policy`.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// # use popcorn::vault::*;
+  /// #
+  /// let vault: Vault<f64> = Vault::new_with_policy(34.5, LockPolicy::Backoff { spins: 64 });
+  /// assert_eq!(*vault.try_lock().unwrap(), 34.5);
+  /// ```
+  pub fn new_with_policy(t: T, policy: LockPolicy) -> Vault<T> {
     let inner = Arc::new(Inner {
+      id: NEXT_ID.fetch_add(1, Ordering::SeqCst),
+      policy: policy,
       lock: spin::Mutex::new(InnerLock {
         locked: false,
-        waiting: Vec::with_capacity(2),
+        readers: 0,
+        waiting: VecDeque::with_capacity(2),
+        next_waiter: 0,
+        waiting_readers: Vec::new(),
+        poisoned: false,
+        handles: 1,
       }),
       data: UnsafeCell::new(t)
     });
@@ -166,7 +312,8 @@ impl<T> Vault<T> {
   /// ```
   pub fn lock(&self) -> VaultAcquire<T> {
     VaultAcquire {
-      inner: self.inner.clone()
+      inner: self.inner.clone(),
+      waiter: None
     }
   }
 
@@ -190,20 +337,287 @@ impl<T> Vault<T> {
   /// #
   /// # assert_eq!(value, 34.5);
   /// ```
-  pub fn try_lock(&self) -> Result<VaultAcquired<T>, ()> {
-    let mut lock = self.inner.lock.lock();
+  pub fn try_lock(&self) -> Result<VaultAcquired<T>, LockError<VaultAcquired<T>>> {
+    let mut lock = self.inner.lock_state();
 
-    if !lock.locked {
-      lock.locked = true;
-      Ok(VaultAcquired {
-        inner: self.inner.clone()
-      })
+    if lock.is_free() {
+      lock.acquire(&self.inner)
     } else {
-      Err(())
+      Err(LockError::WouldBlock)
+    }
+  }
+
+  /// Trys to lock the `Vault`, blocking the calling thread for at
+  /// most `dur`. Like `try_lock`, this is meant to be used outside
+  /// of the futures API, for instance in `Drop` paths where no
+  /// executor is available. No task is ever parked, so giving up
+  /// leaves nothing behind in the waiting queue.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// # use popcorn::vault::*;
+  /// # use std::time::Duration;
+  /// #
+  /// // Create a new Vault containing a double
+  /// let vault: Vault<f64> = Vault::new(34.5);
+  ///
+  /// // Acquire the lock to the vault
+  /// let acquired = vault.try_lock_for(Duration::from_millis(10)).unwrap();
+  ///
+  /// // A second attempt gives up once the duration has passed
+  /// match vault.try_lock_for(Duration::from_millis(10)) {
+  ///   Err(LockError::TimedOut) => { },
+  ///   _ => panic!("expected a timeout")
+  /// }
+  /// #
+  /// # assert_eq!(*acquired, 34.5);
+  /// ```
+  pub fn try_lock_for(&self, dur: Duration) -> Result<VaultAcquired<T>, LockError<VaultAcquired<T>>> {
+    let start = Instant::now();
+
+    loop {
+      match self.try_lock() {
+        Err(LockError::WouldBlock) => { },
+        r => return r
+      }
+
+      if start.elapsed() >= dur {
+        return Err(LockError::TimedOut)
+      }
+
+      thread::yield_now();
+    }
+  }
+
+  /// Locks the `Vault` immediately and runs `f` on its data, releasing
+  /// the lock once `f` returns. Fails when the `Vault` is already
+  /// locked or poisoned.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// # use popcorn::vault::*;
+  /// #
+  /// let vault: Vault<f64> = Vault::new(34.5);
+  /// let doubled = vault.with(|v| { *v *= 2.0; *v }).unwrap();
+  /// #
+  /// # assert_eq!(doubled, 69.0);
+  /// ```
+  pub fn with<R, F: FnOnce(&mut T) -> R>(&self, f: F) -> Result<R, ()> {
+    let mut guard = try!(self.try_lock().map_err(|_| ()));
+    Ok(f(&mut guard))
+  }
+
+  /// Whether an owner of the lock panicked while holding it.
+  pub fn is_poisoned(&self) -> bool {
+    self.inner.lock_state().poisoned
+  }
+
+  /// Number of tasks parked waiting for exclusive access.
+  pub fn waiters(&self) -> usize {
+    self.inner.lock_state().waiting.len()
+  }
+
+  /// Whether the `Vault` is currently acquired for exclusive access.
+  pub fn is_locked(&self) -> bool {
+    self.inner.lock_state().locked
+  }
+
+  /// The `LockPolicy` threads use to wait on this `Vault`'s internal lock.
+  pub fn policy(&self) -> LockPolicy {
+    self.inner.policy
+  }
+
+  /// An id shared by every handle to this `Vault` and no other.
+  pub fn id(&self) -> usize {
+    self.inner.id
+  }
+
+  /// Whether both handles refer to the same `Vault`, in which case
+  /// locking both at once would never complete.
+  pub fn ptr_eq(this: &Vault<T>, other: &Vault<T>) -> bool {
+    Arc::ptr_eq(&this.inner, &other.inner)
+  }
+
+  /// Consumes the `Vault`, returning the underlying data. This only
+  /// succeeds when this is the last handle to the `Vault` and it is
+  /// not locked, otherwise the `Vault` is handed back unchanged.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// # use popcorn::vault::*;
+  /// #
+  /// let vault: Vault<f64> = Vault::new(34.5);
+  /// let other = vault.clone();
+  ///
+  /// // Another handle is still alive
+  /// let vault = vault.into_inner().unwrap_err();
+  /// drop(other);
+  ///
+  /// assert_eq!(vault.into_inner().unwrap(), 34.5);
+  /// ```
+  pub fn into_inner(self) -> Result<T, Vault<T>> {
+    if Arc::strong_count(&self.inner) != 1 || self.inner.lock_state().locked {
+      return Err(self)
+    }
+
+    // We are the only owner, so unwrapping after the drop cannot fail
+    let inner = self.inner.clone();
+    drop(self);
+    match Arc::try_unwrap(inner) {
+      Ok(inner) => Ok(inner.data.into_inner()),
+      Err(_) => unreachable!()
+    }
+  }
+
+  /// Mutably borrow the underlying data without locking. This is only
+  /// possible when this is the last handle to the `Vault`, which also
+  /// guarantees that nobody else holds the lock.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// # use popcorn::vault::*;
+  /// #
+  /// let mut vault: Vault<f64> = Vault::new(34.5);
+  /// *vault.get_mut().unwrap() = 42.0;
+  ///
+  /// let other = vault.clone();
+  /// assert!(vault.get_mut().is_none());
+  /// # drop(other);
+  /// # assert_eq!(vault.into_inner().unwrap(), 42.0);
+  /// ```
+  pub fn get_mut(&mut self) -> Option<&mut T> {
+    Arc::get_mut(&mut self.inner).map(|inner| unsafe { &mut *inner.data.get() })
+  }
+
+  /// Lock the `Vault` for shared, read-only access to its data.
+  /// This operation does not block and will return a
+  /// `VaultRead` object, which is a future that resolves to a
+  /// `VaultReadGuard`. Many read guards may be held at the same
+  /// time, but they exclude any `VaultAcquired`.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// # extern crate futures;
+  /// # extern crate popcorn;
+  /// #
+  /// # use popcorn::vault::*;
+  /// # use futures::Future;
+  /// #
+  /// # fn main() {
+  /// // Create a new Vault containing a double
+  /// let vault: Vault<f64> = Vault::new(42.0);
+  ///
+  /// // Acquire two shared read guards at once
+  /// let r1 = vault.lock_shared().wait().unwrap();
+  /// let r2 = vault.lock_shared().wait().unwrap();
+  ///
+  /// // Writers are excluded while readers are alive
+  /// assert!(vault.try_lock().is_err());
+  /// #
+  /// # assert_eq!(*r1 + *r2, 84.0);
+  /// # }
+  /// ```
+  pub fn lock_shared(&self) -> VaultRead<T> {
+    VaultRead {
+      inner: self.inner.clone(),
+      waiter: None
+    }
+  }
+}
+
+impl<T> Inner<T> {
+  fn lock_state(&self) -> spin::MutexGuard<InnerLock> {
+    match self.policy {
+      LockPolicy::Spin => self.lock.lock(),
+      LockPolicy::Backoff { spins } => {
+        let mut attempts = 0;
+        loop {
+          if let Some(guard) = self.lock.try_lock() {
+            return guard
+          }
+
+          if attempts < spins {
+            attempts += 1;
+            hint::spin_loop();
+          } else {
+            thread::yield_now();
+          }
+        }
+      }
     }
   }
 }
 
+impl Default for LockPolicy {
+  fn default() -> LockPolicy { LockPolicy::Spin }
+}
+
+impl InnerLock {
+  /// Take the exclusive lock, which must be free, reporting poisoning.
+  fn acquire<T>(&mut self, inner: &Arc<Inner<T>>) -> Result<VaultAcquired<T>, LockError<VaultAcquired<T>>> {
+    self.locked = true;
+    self.handles += 1;
+    let acquired = VaultAcquired {
+      inner: inner.clone()
+    };
+
+    if self.poisoned {
+      Err(LockError::Poisoned(PoisonError { guard: acquired }))
+    } else {
+      Ok(acquired)
+    }
+  }
+
+  /// Drop a handle or guard, closing the vault if it was the last.
+  fn release_handle(&mut self) {
+    self.handles -= 1;
+    if self.is_closed() {
+      for (_, task) in self.waiting.drain(..) {
+        task.unpark();
+      }
+
+      for (_, task) in self.waiting_readers.drain(..) {
+        task.unpark();
+      }
+    }
+  }
+
+  /// Wake the writer at the front of the queue, or every waiting
+  /// reader when no writer is waiting.
+  fn wake_next(&mut self) {
+    match self.waiting.front() {
+      Some(&(_, ref task)) => task.unpark(),
+      None => {
+        for (_, task) in self.waiting_readers.drain(..) {
+          task.unpark();
+        }
+      }
+    }
+  }
+
+  /// Whether a writer may acquire the lock right now.
+  fn is_free(&self) -> bool {
+    !self.locked && self.readers == 0
+  }
+
+  /// Whether every `Vault` handle and guard has been dropped, so that
+  /// nobody is left to release the lock to pending futures.
+  fn is_closed(&self) -> bool {
+    self.handles == 0
+  }
+
+  /// Whether a new reader may acquire the lock right now. Readers
+  /// may never get ahead of a waiting writer.
+  fn is_readable(&self) -> bool {
+    !self.locked && self.waiting.is_empty()
+  }
+}
+
 /// Move any data type into a `Vault`.
 impl<T> From<T> for Vault<T> {
   fn from(t: T) -> Vault<T> {
@@ -213,24 +627,167 @@ impl<T> From<T> for Vault<T> {
 
 impl<T> Future for VaultAcquire<T> {
   type Item = VaultAcquired<T>;
+  type Error = LockError<VaultAcquired<T>>;
+
+  fn poll(&mut self) -> Poll<VaultAcquired<T>, LockError<VaultAcquired<T>>> {
+    let mut lock = self.inner.lock_state();
+
+    // Only the front of the queue may take a free lock, anyone else
+    // queues up behind it. Once parked, we fail when the vault closes,
+    // but a lock that was never waited for is simply taken
+    let first = lock.waiting.front().map(|w| w.0);
+    if lock.is_closed() && self.waiter.is_some() {
+      Err(LockError::Closed)
+    } else if lock.is_free() && (first.is_none() || first == self.waiter) {
+      if self.waiter.take().is_some() {
+        lock.waiting.pop_front();
+      }
+      lock.acquire(&self.inner).map(Async::Ready)
+    } else {
+      let task = task::park();
+      let id = match self.waiter {
+        Some(id) => id,
+        None => {
+          let id = lock.next_waiter;
+          lock.next_waiter = lock.next_waiter.wrapping_add(1);
+          self.waiter = Some(id);
+          id
+        }
+      };
+
+      // Replace our task if we are already queued from an earlier poll
+      match lock.waiting.iter().position(|w| w.0 == id) {
+        Some(i) => lock.waiting[i].1 = task,
+        None => lock.waiting.push_back((id, task))
+      }
+
+      Ok(Async::NotReady)
+    }
+  }
+}
+
+impl<T> Drop for VaultAcquire<T> {
+  /// Leave the waiting queue when a pending lock is dropped. If we were
+  /// at the front of a free lock, we had been woken, so the wakeup is
+  /// passed on to the next waiter. If we were the last writer waiting,
+  /// readers held back by us are woken.
+  fn drop(&mut self) {
+    let id = match self.waiter {
+      Some(id) => id,
+      None => return
+    };
+
+    let mut lock = self.inner.lock_state();
+    if let Some(i) = lock.waiting.iter().position(|w| w.0 == id) {
+      lock.waiting.remove(i);
+      if i == 0 && lock.is_free() {
+        lock.wake_next();
+      } else if lock.is_readable() {
+        // Readers parked behind us may now join the active readers
+        for (_, task) in lock.waiting_readers.drain(..) {
+          task.unpark();
+        }
+      }
+    }
+  }
+}
+
+impl<T> Future for VaultRead<T> {
+  type Item = VaultReadGuard<T>;
   type Error = ();
 
-  fn poll(&mut self) -> Poll<VaultAcquired<T>, ()> {
-    let mut lock = self.inner.lock.lock();
+  fn poll(&mut self) -> Poll<VaultReadGuard<T>, ()> {
+    let mut lock = self.inner.lock_state();
 
-    if !lock.locked {
-      lock.locked = true;
-      Ok(VaultAcquired {
+    if lock.is_closed() && self.waiter.is_some() {
+      Err(())
+    } else if lock.is_readable() {
+      self.waiter = None;
+      lock.readers += 1;
+      lock.handles += 1;
+      Ok(VaultReadGuard {
         inner: self.inner.clone()
       }.into())
     } else {
       let task = task::park();
-      lock.waiting.push(task);
+      let id = match self.waiter {
+        Some(id) => id,
+        None => {
+          let id = lock.next_waiter;
+          lock.next_waiter = lock.next_waiter.wrapping_add(1);
+          self.waiter = Some(id);
+          id
+        }
+      };
+
+      match lock.waiting_readers.iter().position(|w| w.0 == id) {
+        Some(i) => lock.waiting_readers[i].1 = task,
+        None => lock.waiting_readers.push((id, task))
+      }
+
       Ok(Async::NotReady)
     }
   }
 }
 
+impl<T> Drop for VaultRead<T> {
+  /// Leave the waiting readers when a pending read lock is dropped.
+  /// Readers are all woken together, so there is no wakeup to pass on.
+  fn drop(&mut self) {
+    if let Some(id) = self.waiter {
+      self.inner.lock_state().waiting_readers.retain(|w| w.0 != id);
+    }
+  }
+}
+
+impl<T> VaultAcquired<T> {
+  /// Whether this guard is the only thing left referring to the
+  /// `Vault`, with no handles or pending lock futures.
+  pub fn is_unique(&self) -> bool {
+    Arc::strong_count(&self.inner) == 1
+  }
+
+  /// Project the locked data onto a part of it, such as a single
+  /// field. The returned `MappedGuard` keeps the `Vault` locked
+  /// until it is dropped.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// # use popcorn::vault::*;
+  /// #
+  /// let vault: Vault<(f64, Vec<f64>)> = Vault::new((34.5, vec![]));
+  ///
+  /// // Only touch the second field
+  /// let mut values = vault.try_lock().unwrap().map(|v| &mut v.1);
+  /// values.push(42.0);
+  ///
+  /// // Still locked while the mapped guard is alive
+  /// assert!(vault.try_lock().is_err());
+  /// drop(values);
+  /// #
+  /// # assert_eq!(vault.try_lock().unwrap().1, vec![42.0]);
+  /// ```
+  pub fn map<U, F: FnOnce(&mut T) -> &mut U>(mut self, f: F) -> MappedGuard<T, U> {
+    let data = f(&mut *self) as *mut U;
+
+    MappedGuard {
+      _acquired: self,
+      data: data
+    }
+  }
+}
+
+impl<T, U> Deref for MappedGuard<T, U> {
+  type Target = U;
+
+  fn deref(&self) -> &U { unsafe { &*self.data } }
+}
+
+impl<T, U> DerefMut for MappedGuard<T, U> {
+  fn deref_mut(&mut self) -> &mut U { unsafe { &mut *self.data } }
+}
+
 impl<T> Deref for VaultAcquired<T> {
   type Target = T;
 
@@ -243,23 +800,416 @@ impl<T> DerefMut for VaultAcquired<T> {
 
 impl<T> Drop for VaultAcquired<T> {
   /// Release the lock on the `Vault` when `VaultAcquired` is dropped.
+  /// Dropping while panicking poisons the `Vault`.
   fn drop(&mut self) {
-    let mut lock = self.inner.lock.lock();
+    let mut lock = self.inner.lock_state();
     assert!(lock.locked);
 
+    if thread::panicking() {
+      lock.poisoned = true;
+    }
+
     lock.locked = false;
-    match lock.waiting.pop() {
-      Some(task) => task.unpark(),
-      None => { }
+    lock.wake_next();
+    lock.release_handle();
+  }
+}
+
+impl<T> Deref for VaultReadGuard<T> {
+  type Target = T;
+
+  fn deref(&self) -> &T { unsafe { &*self.inner.data.get() } }
+}
+
+impl<T> Drop for VaultReadGuard<T> {
+  /// Release this reader. The last reader out wakes a waiting writer.
+  fn drop(&mut self) {
+    let mut lock = self.inner.lock_state();
+    assert!(lock.readers > 0);
+
+    lock.readers -= 1;
+    if lock.readers == 0 {
+      lock.wake_next();
+    }
+
+    lock.release_handle();
+  }
+}
+
+impl<G> PoisonError<G> {
+  /// Recover the guard despite the poisoning.
+  pub fn into_inner(self) -> G { self.guard }
+
+  /// Borrow the guard despite the poisoning.
+  pub fn get_ref(&self) -> &G { &self.guard }
+
+  /// Mutably borrow the guard despite the poisoning.
+  pub fn get_mut(&mut self) -> &mut G { &mut self.guard }
+}
+
+impl<G> fmt::Debug for PoisonError<G> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "PoisonError {{ .. }}")
+  }
+}
+
+impl<G> fmt::Debug for LockError<G> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      LockError::Poisoned(ref err) => write!(f, "Poisoned({:?})", err),
+      LockError::WouldBlock => write!(f, "WouldBlock"),
+      LockError::TimedOut => write!(f, "TimedOut"),
+      LockError::Closed => write!(f, "Closed")
     }
   }
 }
 
 impl<T> From<VaultAcquired<T>> for Vault<T> {
   fn from(va: VaultAcquired<T>) -> Vault<T> {
+    va.inner.lock_state().handles += 1;
+
     Vault {
       inner: va.inner.clone()
     }
   }
 }
 
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use std::thread;
+  use std::sync::mpsc;
+  use futures::{future, Future};
+
+  #[test]
+  fn test_shared_readers() {
+    let vault: Vault<f64> = Vault::new(12.5);
+
+    let r1 = vault.lock_shared().wait().unwrap();
+    let r2 = vault.lock_shared().wait().unwrap();
+
+    {
+      let lock = vault.inner.lock.lock();
+      assert_eq!(lock.readers, 2);
+      assert!(lock.waiting_readers.is_empty());
+    }
+
+    assert_eq!(*r1, 12.5);
+    assert_eq!(*r2, 12.5);
+    assert!(vault.try_lock().is_err());
+
+    drop(r1);
+    drop(r2);
+    assert!(vault.try_lock().is_ok());
+  }
+
+  #[test]
+  fn test_waiting_writer_blocks_readers() {
+    let vault: Vault<f64> = Vault::new(12.5);
+    let r1 = vault.lock_shared().wait().unwrap();
+
+    let writer_vault = vault.clone();
+    let writer = thread::spawn(move || {
+      let mut w = writer_vault.lock().wait().unwrap();
+      *w = 42.0;
+    });
+
+    while vault.inner.lock.lock().waiting.is_empty() {
+      thread::yield_now();
+    }
+    assert!(!vault.inner.lock.lock().is_readable());
+
+    drop(r1);
+    writer.join().unwrap();
+
+    let r2 = vault.lock_shared().wait().unwrap();
+    assert_eq!(*r2, 42.0);
+  }
+
+  #[test]
+  fn test_queued_writer_beats_new_reader() {
+    let vault: Vault<f64> = Vault::new(12.5);
+    let guard = vault.try_lock().unwrap();
+
+    let mut writer = vault.lock();
+    let mut reader = vault.lock_shared();
+    future::poll_fn(|| {
+      assert!(writer.poll().unwrap().is_not_ready());
+      Ok::<_, ()>(Async::Ready(()))
+    }).wait().unwrap();
+
+    // Free and without readers, but the writer is still first in line
+    drop(guard);
+    future::poll_fn(|| {
+      assert!(reader.poll().unwrap().is_not_ready());
+      Ok::<_, ()>(Async::Ready(()))
+    }).wait().unwrap();
+
+    let mut w = writer.wait().unwrap();
+    *w = 42.0;
+    drop(w);
+    assert_eq!(*reader.wait().unwrap(), 42.0);
+  }
+
+  #[test]
+  fn test_waiting_fifo() {
+    let vault: Vault<Vec<usize>> = Vault::new(Vec::new());
+    let guard = vault.try_lock().unwrap();
+
+    let mut handles = Vec::new();
+    for i in 0..8 {
+      let v = vault.clone();
+      handles.push(thread::spawn(move || {
+        let mut order = v.lock().wait().unwrap();
+        order.push(i);
+      }));
+
+      // Wait for this task to park before queueing the next one
+      while vault.inner.lock.lock().waiting.len() < i + 1 {
+        thread::yield_now();
+      }
+    }
+
+    drop(guard);
+    for h in handles {
+      h.join().unwrap();
+    }
+
+    let order = vault.try_lock().unwrap();
+    assert_eq!(*order, (0..8).collect::<Vec<usize>>());
+  }
+
+  #[test]
+  fn test_repoll_does_not_duplicate_waiter() {
+    let vault: Vault<f64> = Vault::new(12.5);
+    let guard = vault.try_lock().unwrap();
+
+    let mut acquire = vault.lock();
+    let waiting = future::poll_fn(|| {
+      assert!(acquire.poll().unwrap().is_not_ready());
+      assert!(acquire.poll().unwrap().is_not_ready());
+      Ok::<_, ()>(Async::Ready(vault.inner.lock.lock().waiting.len()))
+    }).wait().unwrap();
+    assert_eq!(waiting, 1);
+
+    drop(guard);
+    assert_eq!(*acquire.wait().unwrap(), 12.5);
+  }
+
+  #[test]
+  fn test_try_lock_for() {
+    let vault: Vault<f64> = Vault::new(12.5);
+    let guard = vault.try_lock().unwrap();
+
+    let waiter_vault = vault.clone();
+    let waiter = thread::spawn(move || {
+      waiter_vault.try_lock_for(Duration::from_secs(10)).map(|v| *v).ok()
+    });
+
+    thread::sleep(Duration::from_millis(20));
+    drop(guard);
+    assert_eq!(waiter.join().unwrap(), Some(12.5));
+
+    let guard = vault.try_lock().unwrap();
+    match vault.try_lock_for(Duration::from_millis(5)) {
+      Err(LockError::TimedOut) => { },
+      r => panic!("expected a timeout, got {:?}", r.map(|_| ()))
+    }
+    assert!(vault.inner.lock.lock().waiting.is_empty());
+    drop(guard);
+  }
+
+  #[test]
+  fn test_dropped_vault_fails_pending_lock() {
+    let vault: Vault<f64> = Vault::new(12.5);
+    let guard = vault.try_lock().unwrap();
+
+    let acquire = vault.lock();
+    let waiter = thread::spawn(move || acquire.wait().is_err());
+
+    while vault.inner.lock.lock().waiting.is_empty() {
+      thread::yield_now();
+    }
+
+    // The guard can still release the lock, so the wait goes on
+    drop(vault);
+    assert_eq!(guard.inner.lock.lock().waiting.len(), 1);
+
+    drop(guard);
+    assert!(waiter.join().unwrap());
+  }
+
+  #[test]
+  fn test_poisoned_on_panic() {
+    let vault: Vault<f64> = Vault::new(12.5);
+
+    let panic_vault = vault.clone();
+    let r = thread::spawn(move || {
+      let mut guard = panic_vault.try_lock().unwrap();
+      *guard = 42.0;
+      panic!("kernel failed mid-write");
+    }).join();
+    assert!(r.is_err());
+    assert!(vault.is_poisoned());
+
+    match vault.try_lock() {
+      Err(LockError::Poisoned(err)) => assert_eq!(*err.into_inner(), 42.0),
+      r => panic!("expected a poisoned lock, got {:?}", r.map(|_| ()))
+    }
+
+    match vault.lock().wait() {
+      Err(LockError::Poisoned(err)) => assert_eq!(*err.get_ref().deref(), 42.0),
+      r => panic!("expected a poisoned lock, got {:?}", r.map(|_| ()))
+    }
+  }
+
+  #[test]
+  fn test_backoff_under_contention() {
+    let vault: Vault<usize> = Vault::new_with_policy(0, LockPolicy::Backoff { spins: 4 });
+    assert_eq!(vault.policy(), LockPolicy::Backoff { spins: 4 });
+
+    // More threads than cores all fighting over the same vault
+    let threads: Vec<_> = (0..8).map(|_| {
+      let vault = vault.clone();
+      thread::spawn(move || {
+        for _ in 0..500 {
+          let mut guard = vault.lock().wait().unwrap();
+          *guard += 1;
+        }
+      })
+    }).collect();
+
+    for t in threads {
+      t.join().unwrap();
+    }
+    assert_eq!(*vault.try_lock().unwrap(), 4000);
+  }
+
+  #[test]
+  fn test_waiters() {
+    let vault: Vault<f64> = Vault::new(12.5);
+    assert!(!vault.is_locked());
+    let guard = vault.try_lock().unwrap();
+    assert!(vault.is_locked());
+
+    let handles: Vec<_> = (0..2).map(|_| {
+      let v = vault.clone();
+      thread::spawn(move || { v.lock().wait().unwrap(); })
+    }).collect();
+
+    while vault.waiters() < 2 {
+      thread::yield_now();
+    }
+    assert_eq!(vault.waiters(), 2);
+
+    drop(guard);
+    for h in handles {
+      h.join().unwrap();
+    }
+    assert_eq!(vault.waiters(), 0);
+    assert!(!vault.is_locked());
+  }
+
+  #[test]
+  fn test_with() {
+    let vault: Vault<Vec<usize>> = Vault::new(vec![1]);
+    assert_eq!(vault.with(|v| { v.push(2); v.len() }), Ok(2));
+    assert_eq!(*vault.try_lock().unwrap(), vec![1, 2]);
+
+    let guard = vault.try_lock().unwrap();
+    assert_eq!(vault.with(|v| v.len()), Err(()));
+    drop(guard);
+    assert!(!vault.is_locked());
+  }
+
+  #[test]
+  fn test_dropped_acquire_leaves_queue() {
+    let vault: Vault<f64> = Vault::new(12.5);
+    let guard = vault.try_lock().unwrap();
+
+    let mut first = vault.lock();
+    let mut second = vault.lock();
+    future::poll_fn(|| {
+      assert!(first.poll().unwrap().is_not_ready());
+      assert!(second.poll().unwrap().is_not_ready());
+      Ok::<_, ()>(Async::Ready(()))
+    }).wait().unwrap();
+    assert_eq!(vault.waiters(), 2);
+
+    // Dropped while still queued
+    drop(second);
+    assert_eq!(vault.waiters(), 1);
+
+    // Woken by the release, then dropped before acquiring, which must
+    // pass the wakeup on to the waiter behind it
+    let third = vault.clone();
+    let waiter = thread::spawn(move || *third.lock().wait().unwrap());
+    while vault.waiters() < 2 {
+      thread::yield_now();
+    }
+
+    drop(guard);
+    drop(first);
+    assert_eq!(waiter.join().unwrap(), 12.5);
+    assert_eq!(vault.waiters(), 0);
+  }
+
+  #[test]
+  fn test_woken_waiter_keeps_its_place() {
+    let vault: Vault<f64> = Vault::new(12.5);
+    let guard = vault.try_lock().unwrap();
+
+    let mut first = vault.lock();
+    let mut second = vault.lock();
+    future::poll_fn(|| {
+      assert!(first.poll().unwrap().is_not_ready());
+      assert!(second.poll().unwrap().is_not_ready());
+      Ok::<_, ()>(Async::Ready(()))
+    }).wait().unwrap();
+
+    // The first waiter is woken, but someone else takes the lock
+    // before it is polled again
+    drop(guard);
+    let guard = vault.try_lock().unwrap();
+
+    let ids: Vec<usize> = vault.inner.lock.lock().waiting.iter().map(|w| w.0).collect();
+    assert_eq!(ids, vec![first.waiter.unwrap(), second.waiter.unwrap()]);
+
+    // Once free again, the second waiter can not jump the queue
+    drop(guard);
+    future::poll_fn(|| {
+      assert!(second.poll().unwrap().is_not_ready());
+      Ok::<_, ()>(Async::Ready(()))
+    }).wait().unwrap();
+    drop(first.wait().unwrap());
+    second.wait().unwrap();
+    assert_eq!(vault.waiters(), 0);
+  }
+
+  #[test]
+  fn test_dropped_writer_wakes_readers() {
+    let vault: Vault<f64> = Vault::new(12.5);
+    let r1 = vault.lock_shared().wait().unwrap();
+
+    let mut writer = vault.lock();
+    future::poll_fn(|| {
+      assert!(writer.poll().unwrap().is_not_ready());
+      Ok::<_, ()>(Async::Ready(()))
+    }).wait().unwrap();
+
+    let (tx, rx) = mpsc::channel();
+    let reader_vault = vault.clone();
+    let reader = thread::spawn(move || {
+      tx.send(*reader_vault.lock_shared().wait().unwrap()).unwrap();
+    });
+    while vault.inner.lock.lock().waiting_readers.is_empty() {
+      thread::yield_now();
+    }
+
+    drop(writer);
+    assert_eq!(rx.recv_timeout(Duration::from_secs(10)), Ok(12.5));
+    reader.join().unwrap();
+    drop(r1);
+  }
+}